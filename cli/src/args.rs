@@ -0,0 +1,190 @@
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Parser, Debug)]
+#[command(name = "beeraf", version, about = "Run beeraf houses and raffles from the command line")]
+pub struct Args {
+    #[arg(long, short = 'u', global = true, default_value = "http://127.0.0.1:8899")]
+    pub url: String,
+
+    #[arg(
+        long,
+        short = 'k',
+        global = true,
+        default_value = "~/.config/solana/id.json",
+        help = "Fee payer and signer of every transaction (the house authority, maker or buyer)"
+    )]
+    pub keypair: String,
+
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    #[arg(long, global = true, help = "Print the signed transactions in base64 instead of sending them")]
+    pub dry_run: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Table,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[command(subcommand, about = "Set up and manage a house")]
+    House(HouseCommand),
+    #[command(subcommand, about = "Create, inspect and settle raffles")]
+    Raffle(RaffleCommand),
+    #[command(subcommand, about = "Buy, scratch and list tickets")]
+    Ticket(TicketCommand),
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct HouseArg {
+    #[arg(long, help = "Defaults to the keypair, so house operators can leave it out")]
+    pub house: Option<Pubkey>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HouseCommand {
+    #[command(about = "Initialize a house with the keypair as authority")]
+    Init {
+        #[arg(long, help = "Creation fee paid by makers, in lamports")]
+        fee: u64,
+        #[arg(long, default_value_t = 0)]
+        house_fee_bps: u16,
+        #[arg(long)]
+        max_raffle_fee_bps: Option<u16>,
+        #[arg(long)]
+        min_ticket_price: Option<u64>,
+        #[arg(long)]
+        max_ticket_price: Option<u64>,
+        #[arg(long)]
+        min_slot_interval: Option<u64>,
+        #[arg(long)]
+        max_slot_interval: Option<u64>,
+        #[arg(long)]
+        cancel_refund_bps: Option<u16>,
+    },
+    #[command(about = "Show the config and treasury of a house")]
+    Show {
+        #[command(flatten)]
+        house: HouseArg,
+    },
+    #[command(about = "Withdraw lamports (or tokens of a mint) from the treasury")]
+    Withdraw {
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, help = "Defaults to the keypair")]
+        destination: Option<Pubkey>,
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RaffleCommand {
+    #[command(about = "Create a raffle with the keypair as maker")]
+    Create {
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        uri: String,
+        #[arg(long)]
+        ticket_price: u64,
+        #[arg(long, default_value_t = 0, help = "Maker fee in basis points of every ticket")]
+        raffle_fee: u64,
+        #[arg(long)]
+        capacity: u32,
+        #[arg(long, conflicts_with = "end_timestamp", required_unless_present = "end_timestamp")]
+        slot_interval: Option<u64>,
+        #[arg(long)]
+        end_timestamp: Option<i64>,
+        #[arg(long)]
+        start_timestamp: Option<i64>,
+        #[arg(long)]
+        end_on_sell_out: bool,
+        #[arg(long)]
+        min_tickets: Option<u32>,
+        #[arg(long, value_delimiter = ',', help = "Basis points of the prize pool for each place, e.g. 5000,3000,2000")]
+        prize_tiers: Vec<u16>,
+        #[arg(long)]
+        payment_mint: Option<Pubkey>,
+        #[arg(long, conflicts_with = "prize_mint", help = "mpl-core asset escrowed as prize for the first place")]
+        prize_asset: Option<Pubkey>,
+        #[arg(long, requires = "prize_amount")]
+        prize_mint: Option<Pubkey>,
+        #[arg(long)]
+        prize_amount: Option<u64>,
+        #[arg(
+            long,
+            help = "Secret seed in hex, a random one is generated when left out (keep it, the raffle can not be solved without it)"
+        )]
+        seed: Option<String>,
+    },
+    #[command(about = "List the raffles of a house")]
+    List {
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long)]
+        maker: Option<Pubkey>,
+    },
+    #[command(about = "Show a raffle")]
+    Show {
+        raffle: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+    },
+    #[command(about = "Capture the entropy if needed and solve a raffle with its seed, run it again once the entropy slot is produced")]
+    Solve {
+        raffle: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long)]
+        seed: String,
+    },
+    #[command(about = "Close a settled raffle and recover its rent")]
+    Close {
+        raffle: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TicketCommand {
+    #[command(about = "Buy tickets of a raffle")]
+    Buy {
+        raffle: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long, default_value_t = 1)]
+        quantity: u32,
+        #[arg(long, default_value = "Ticket")]
+        name: String,
+        #[arg(long, default_value = "")]
+        uri: String,
+    },
+    #[command(about = "Scratch a ticket and claim its prize when it won")]
+    Scratch {
+        raffle: Pubkey,
+        ticket: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+    },
+    #[command(about = "List the tickets of a raffle")]
+    List {
+        raffle: Pubkey,
+        #[command(flatten)]
+        house: HouseArg,
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey,
+    signature::Signature, sysvar::clock::Clock, transaction::Transaction,
+};
+
+// Everything the commands need from a cluster, so they run the same against
+// an RPC node or an in-process bank
+pub trait Backend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>>;
+
+    fn get_program_accounts(&self, program: &Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>>;
+
+    fn get_clock(&self) -> Result<Clock>;
+
+    fn latest_blockhash(&self) -> Result<Hash>;
+
+    fn send_transaction(&self, transaction: &Transaction) -> Result<Signature>;
+}
+
+pub struct RpcBackend {
+    client: RpcClient,
+}
+
+impl RpcBackend {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+        }
+    }
+}
+
+impl Backend for RpcBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        Ok(self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())?
+            .value)
+    }
+
+    fn get_program_accounts(&self, program: &Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>> {
+        Ok(self.client.get_program_accounts_with_config(
+            program,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.client.commitment()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?)
+    }
+
+    fn get_clock(&self) -> Result<Clock> {
+        let account = self.client.get_account(&solana_sdk::sysvar::clock::ID)?;
+
+        Ok(bincode::deserialize(&account.data)?)
+    }
+
+    fn latest_blockhash(&self) -> Result<Hash> {
+        Ok(self.client.get_latest_blockhash()?)
+    }
+
+    fn send_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        Ok(self.client.send_and_confirm_transaction(transaction)?)
+    }
+}
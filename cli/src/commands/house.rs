@@ -0,0 +1,86 @@
+use anyhow::Result;
+use beeraf_client::{decode_config, find_config, find_treasury, initialize, withdraw_treasury, Prize, RafflePolicy};
+use serde_json::{json, Value};
+
+use super::{object, Context};
+use crate::args::HouseCommand;
+
+pub fn run(context: &Context, command: &HouseCommand) -> Result<Value> {
+    match command {
+        HouseCommand::Init {
+            fee,
+            house_fee_bps,
+            max_raffle_fee_bps,
+            min_ticket_price,
+            max_ticket_price,
+            min_slot_interval,
+            max_slot_interval,
+            cancel_refund_bps,
+        } => {
+            let house = context.pubkey();
+
+            let policy = RafflePolicy {
+                max_raffle_fee_bps: *max_raffle_fee_bps,
+                min_ticket_price: *min_ticket_price,
+                max_ticket_price: *max_ticket_price,
+                min_slot_interval: *min_slot_interval,
+                max_slot_interval: *max_slot_interval,
+                cancel_refund_bps: *cancel_refund_bps,
+            };
+
+            let mut result = object([
+                ("house", house.to_string().into()),
+                ("treasury", find_treasury(&house).0.to_string().into()),
+                ("config", find_config(&house).0.to_string().into()),
+            ]);
+
+            result.extend(context.send(&[initialize(&house, *fee, *house_fee_bps, policy)], &[])?);
+
+            Ok(result.into())
+        }
+        HouseCommand::Show { house } => {
+            let house = context.house(house);
+            let (config_address, _) = find_config(&house);
+            let (treasury, _) = find_treasury(&house);
+
+            let config = decode_config(&context.account(&config_address)?.data)?;
+            let treasury_balance = context.backend.get_account(&treasury)?.map_or(0, |account| account.lamports);
+
+            Ok(json!({
+                "house": house.to_string(),
+                "config": config_address.to_string(),
+                "authority": config.authority.to_string(),
+                "pending_authority": config.pending_authority.map(|authority| authority.to_string()),
+                "fee": config.fee,
+                "house_fee_bps": config.house_fee_bps,
+                "max_raffle_fee_bps": config.policy.max_raffle_fee_bps,
+                "min_ticket_price": config.policy.min_ticket_price,
+                "max_ticket_price": config.policy.max_ticket_price,
+                "min_slot_interval": config.policy.min_slot_interval,
+                "max_slot_interval": config.policy.max_slot_interval,
+                "cancel_refund_bps": config.policy.cancel_refund_bps,
+                "treasury": treasury.to_string(),
+                "treasury_balance": treasury_balance,
+            }))
+        }
+        HouseCommand::Withdraw { house, amount, destination, mint } => {
+            let house = context.house(house);
+            let destination = destination.unwrap_or_else(|| context.pubkey());
+
+            let payment = context.raffle_options(*mint, Prize::None)?.payment;
+
+            let mut result = object([
+                ("house", house.to_string().into()),
+                ("destination", destination.to_string().into()),
+                ("amount", (*amount).into()),
+            ]);
+
+            result.extend(context.send(
+                &[withdraw_treasury(&house, &context.pubkey(), &destination, payment.as_ref(), *amount)],
+                &[],
+            )?);
+
+            Ok(result.into())
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use beeraf_client::{decode_raffle_config, EscrowedPrize, Payment, Prize, RaffleConfig, RaffleKeys, RaffleOptions};
+use mpl_core::{accounts::BaseAssetV1, types::UpdateAuthority};
+use serde_json::{Map, Value};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+use crate::{args::HouseArg, backend::Backend};
+
+pub mod house;
+pub mod raffle;
+pub mod ticket;
+
+pub struct Context<'a> {
+    pub backend: &'a dyn Backend,
+    pub keypair: &'a Keypair,
+    pub dry_run: bool,
+}
+
+impl Context<'_> {
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn house(&self, house: &HouseArg) -> Pubkey {
+        house.house.unwrap_or_else(|| self.pubkey())
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Result<Account> {
+        self.backend
+            .get_account(address)?
+            .ok_or_else(|| anyhow!("Account {address} not found"))
+    }
+
+    // The maker is read from the raffle config, every other key derives from the house and raffle
+    pub fn raffle(&self, house: &Pubkey, raffle: &Pubkey) -> Result<(RaffleKeys, RaffleConfig)> {
+        let mut keys = RaffleKeys {
+            house: *house,
+            maker: Pubkey::default(),
+            raffle: *raffle,
+        };
+
+        let raffle_config = decode_raffle_config(&self.account(&keys.raffle_config())?.data)?;
+
+        keys.maker = raffle_config.authority;
+
+        Ok((keys, raffle_config))
+    }
+
+    // Token programs are the owners of the mints, the collection of an asset prize is its update authority
+    pub fn raffle_options(&self, payment_mint: Option<Pubkey>, prize: Prize) -> Result<RaffleOptions> {
+        let payment = payment_mint
+            .map(|mint| -> Result<Payment> {
+                Ok(Payment {
+                    mint,
+                    token_program: self.account(&mint)?.owner,
+                })
+            })
+            .transpose()?;
+
+        let prize = match prize {
+            Prize::None => None,
+            Prize::Asset { asset } => {
+                let collection = match BaseAssetV1::from_bytes(&self.account(&asset)?.data)?.update_authority {
+                    UpdateAuthority::Collection(collection) => Some(collection),
+                    _ => None,
+                };
+
+                Some(EscrowedPrize {
+                    prize,
+                    collection,
+                    token_program: Pubkey::default(),
+                })
+            }
+            Prize::Token { mint, .. } => Some(EscrowedPrize {
+                prize,
+                collection: None,
+                token_program: self.account(&mint)?.owner,
+            }),
+        };
+
+        Ok(RaffleOptions { payment, prize })
+    }
+
+    // Signature of the confirmed transaction, or the signed transaction in base64 on a dry run
+    pub fn submit(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<String> {
+        let mut all_signers = vec![self.keypair];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.pubkey()),
+            &all_signers,
+            self.backend.latest_blockhash()?,
+        );
+
+        match self.dry_run {
+            true => Ok(STANDARD.encode(bincode::serialize(&transaction)?)),
+            false => Ok(self.backend.send_transaction(&transaction)?.to_string()),
+        }
+    }
+
+    pub fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<Map<String, Value>> {
+        let key = match self.dry_run {
+            true => "transaction",
+            false => "signature",
+        };
+
+        Ok(Map::from_iter([(key.to_string(), self.submit(instructions, signers)?.into())]))
+    }
+}
+
+pub fn parse_seed(seed: &str) -> Result<[u8; 32]> {
+    match <[u8; 32]>::try_from(hex::decode(seed)?) {
+        Ok(seed) => Ok(seed),
+        Err(_) => bail!("The seed must be 32 bytes in hex"),
+    }
+}
+
+// Keys and values of a command result, in the order they are given
+pub fn object<const N: usize>(entries: [(&str, Value); N]) -> Map<String, Value> {
+    entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
@@ -0,0 +1,248 @@
+use anchor_lang::Discriminator;
+use anyhow::{bail, Result};
+use beeraf_client::{
+    capture_entropy, close_raffle, create_raffle, decode_raffle_config, find_raffle_config, solve_raffle_signed,
+    CreateRaffleArgs, Prize, RaffleConfig, RaffleEnd, RaffleKeys, RaffleStatus, RandomnessSource,
+};
+use mpl_core::accounts::BaseCollectionV1;
+use serde_json::{json, Value};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    hash::hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use super::{object, parse_seed, Context};
+use crate::args::RaffleCommand;
+
+fn prize(prize: &Prize) -> Value {
+    match prize {
+        Prize::None => Value::Null,
+        Prize::Asset { asset } => json!({ "asset": asset.to_string() }),
+        Prize::Token { mint, amount } => json!({ "mint": mint.to_string(), "amount": amount }),
+    }
+}
+
+fn summary(raffle_config: &RaffleConfig, collection: &BaseCollectionV1) -> Value {
+    json!({
+        "raffle": raffle_config.collection.to_string(),
+        "maker": raffle_config.authority.to_string(),
+        "status": format!("{:?}", raffle_config.status),
+        "sold": raffle_config.sold(collection.num_minted),
+        "capacity": raffle_config.capacity,
+        "ticket_price": raffle_config.ticket_price,
+        "pot": raffle_config.pot,
+    })
+}
+
+pub fn run(context: &Context, command: &RaffleCommand) -> Result<Value> {
+    match command {
+        RaffleCommand::Create {
+            house,
+            name,
+            uri,
+            ticket_price,
+            raffle_fee,
+            capacity,
+            slot_interval,
+            end_timestamp,
+            start_timestamp,
+            end_on_sell_out,
+            min_tickets,
+            prize_tiers,
+            payment_mint,
+            prize_asset,
+            prize_mint,
+            prize_amount,
+            seed,
+        } => {
+            let raffle = Keypair::new();
+
+            let keys = RaffleKeys {
+                house: context.house(house),
+                maker: context.pubkey(),
+                raffle: raffle.pubkey(),
+            };
+
+            let seed = match seed {
+                Some(seed) => parse_seed(seed)?,
+                None => rand::random(),
+            };
+
+            let end = match (slot_interval, end_timestamp) {
+                (Some(slot_interval), _) => RaffleEnd::Slot { slot_interval: *slot_interval },
+                (None, Some(end_timestamp)) => RaffleEnd::UnixTimestamp { end_timestamp: *end_timestamp },
+                (None, None) => bail!("Either --slot-interval or --end-timestamp is required"),
+            };
+
+            let prize = match (prize_asset, prize_mint, prize_amount) {
+                (Some(asset), _, _) => Prize::Asset { asset: *asset },
+                (None, Some(mint), Some(amount)) => Prize::Token { mint: *mint, amount: *amount },
+                _ => Prize::None,
+            };
+
+            let options = context.raffle_options(*payment_mint, prize)?;
+
+            let args = CreateRaffleArgs {
+                name: name.clone(),
+                uri: uri.clone(),
+                ticket_price: *ticket_price,
+                raffle_fee: *raffle_fee,
+                end,
+                start_timestamp: *start_timestamp,
+                end_on_sell_out: *end_on_sell_out,
+                capacity: *capacity,
+                min_tickets: *min_tickets,
+                commitment: hash(&seed).to_bytes(),
+                randomness_source: RandomnessSource::CommitReveal,
+                prize_tiers: prize_tiers.clone(),
+                payment_mint: *payment_mint,
+                prize,
+            };
+
+            let mut result = object([
+                ("raffle", keys.raffle.to_string().into()),
+                ("raffle_config", keys.raffle_config().to_string().into()),
+                ("seed", hex::encode(seed).into()),
+            ]);
+
+            result.extend(context.send(&[create_raffle(&keys, &options, args)], &[&raffle])?);
+
+            Ok(result.into())
+        }
+        RaffleCommand::List { house, maker } => {
+            let house = context.house(house);
+
+            let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                RaffleConfig::DISCRIMINATOR.to_vec(),
+            ))];
+
+            if let Some(maker) = maker {
+                filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(8, maker.to_bytes().to_vec())));
+            }
+
+            let mut raffles: Vec<(Pubkey, RaffleConfig)> = context
+                .backend
+                .get_program_accounts(&beeraf_client::ID, filters)?
+                .into_iter()
+                // Raffle configs of older layouts that were never migrated do not decode
+                .filter_map(|(address, account)| Some((address, decode_raffle_config(&account.data).ok()?)))
+                // Every house shares the program, only the configs derived from this house belong to it
+                .filter(|(address, raffle_config)| find_raffle_config(&house, &raffle_config.collection).0 == *address)
+                .collect();
+
+            raffles.sort_by_key(|(address, _)| *address);
+
+            raffles
+                .iter()
+                .map(|(_, raffle_config)| {
+                    let collection = BaseCollectionV1::from_bytes(&context.account(&raffle_config.collection)?.data)?;
+
+                    Ok(summary(raffle_config, &collection))
+                })
+                .collect::<Result<Value>>()
+        }
+        RaffleCommand::Show { raffle, house } => {
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+
+            let collection = BaseCollectionV1::from_bytes(&context.account(raffle)?.data)?;
+            let clock = context.backend.get_clock()?;
+
+            let mut result = summary(&raffle_config, &collection);
+
+            result.as_object_mut().unwrap().extend(object([
+                ("name", collection.name.into()),
+                ("raffle_config", keys.raffle_config().to_string().into()),
+                ("vault", keys.vault().to_string().into()),
+                ("min_tickets", raffle_config.min_tickets.into()),
+                ("raffle_fee", raffle_config.raffle_fee.into()),
+                ("end_slot", raffle_config.slot.into()),
+                ("end_timestamp", raffle_config.end_timestamp.into()),
+                ("start_timestamp", raffle_config.start_timestamp.into()),
+                ("end_on_sell_out", raffle_config.end_on_sell_out.into()),
+                ("sales_ended", raffle_config.sales_ended(&clock, collection.num_minted).into()),
+                ("payment_mint", raffle_config.payment_mint.map(|mint| mint.to_string()).into()),
+                ("prize", prize(&raffle_config.prize)),
+                ("prize_tiers", json!(raffle_config.tiers())),
+                ("prize_pool", raffle_config.prize_pool.into()),
+                ("winners", json!(raffle_config.winners)),
+            ]));
+
+            Ok(result)
+        }
+        RaffleCommand::Solve { raffle, house, seed } => {
+            let seed = parse_seed(seed)?;
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+
+            if hash(&seed).to_bytes() != raffle_config.commitment {
+                bail!("The seed does not match the commitment of the raffle");
+            }
+
+            let options = context.raffle_options(raffle_config.payment_mint, raffle_config.prize)?;
+
+            let mut sent = vec![];
+
+            // The entropy is captured in its own transaction, as it must land after the deadline
+            if matches!(raffle_config.status, RaffleStatus::Open | RaffleStatus::SoldOut) {
+                let capture = capture_entropy(&keys, &context.pubkey());
+
+                // The first capture only schedules the slot whose hash is captured,
+                // the command is run again once that slot has been produced
+                if raffle_config.entropy_slot == 0 {
+                    let mut result = object([("raffle", raffle.to_string().into())]);
+
+                    result.extend(context.send(&[capture], &[])?);
+
+                    if !context.dry_run {
+                        let (_, raffle_config) = context.raffle(&keys.house, raffle)?;
+
+                        result.insert("entropy_slot".to_string(), raffle_config.entropy_slot.into());
+                    }
+
+                    return Ok(result.into());
+                }
+
+                let slot = context.backend.get_clock()?.slot;
+
+                if slot <= raffle_config.entropy_slot {
+                    bail!("The entropy slot {} has not been produced yet", raffle_config.entropy_slot);
+                }
+
+                sent.push(context.submit(&[capture], &[])?);
+            }
+
+            sent.push(context.submit(
+                &solve_raffle_signed(&keys, &options, &raffle_config, context.keypair, seed),
+                &[],
+            )?);
+
+            let mut result = object([("raffle", raffle.to_string().into())]);
+
+            match context.dry_run {
+                true => {
+                    result.insert("transactions".to_string(), sent.into());
+                }
+                false => {
+                    let (_, raffle_config) = context.raffle(&keys.house, raffle)?;
+
+                    result.insert("winners".to_string(), json!(raffle_config.winners));
+                    result.insert("signatures".to_string(), sent.into());
+                }
+            }
+
+            Ok(result.into())
+        }
+        RaffleCommand::Close { raffle, house } => {
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+            let options = context.raffle_options(raffle_config.payment_mint, raffle_config.prize)?;
+
+            let mut result = object([("raffle", raffle.to_string().into())]);
+
+            result.extend(context.send(&[close_raffle(&keys, &options)], &[])?);
+
+            Ok(result.into())
+        }
+    }
+}
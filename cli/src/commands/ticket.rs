@@ -0,0 +1,118 @@
+use anyhow::Result;
+use beeraf_client::{buy_ticket, decode_ticket_data, decode_ticket_range, scratch_ticket, BuyTicketArgs, RaffleConfig};
+use mpl_core::accounts::BaseAssetV1;
+use serde_json::{json, Value};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use super::{object, Context};
+use crate::args::TicketCommand;
+
+// Offsets in the BaseAssetV1 layout: key, owner, then the update authority
+const ASSET_V1_KEY: u8 = 1;
+const OWNER_OFFSET: usize = 1;
+const UPDATE_AUTHORITY_OFFSET: usize = 33;
+const UPDATE_AUTHORITY_COLLECTION: u8 = 2;
+
+fn ticket_row(address: &Pubkey, data: &[u8], raffle_config_address: &Pubkey, raffle_config: &RaffleConfig) -> Result<Value> {
+    let asset = BaseAssetV1::from_bytes(data)?;
+    let (first, last) = decode_ticket_range(data)?;
+    let ticket_data = decode_ticket_data(data, raffle_config_address)?;
+
+    // Places are numbered from 1, a ticket holding several numbers can win several of them
+    let places: Vec<usize> = raffle_config
+        .winners
+        .iter()
+        .enumerate()
+        .filter(|(_, winner)| (first..=last).contains(*winner))
+        .map(|(place, _)| place + 1)
+        .collect();
+
+    Ok(json!({
+        "ticket": address.to_string(),
+        "owner": asset.owner.to_string(),
+        "first": first,
+        "last": last,
+        "price_paid": ticket_data.price_paid,
+        "scratched": ticket_data.scratched,
+        "claimed_amount": ticket_data.claimed_amount,
+        "refunded": ticket_data.refunded,
+        "places": places,
+    }))
+}
+
+pub fn run(context: &Context, command: &TicketCommand) -> Result<Value> {
+    match command {
+        TicketCommand::Buy { raffle, house, quantity, name, uri } => {
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+            let options = context.raffle_options(raffle_config.payment_mint, raffle_config.prize)?;
+
+            let ticket = Keypair::new();
+
+            let args = BuyTicketArgs {
+                name: name.clone(),
+                uri: uri.clone(),
+                quantity: *quantity,
+            };
+
+            let mut result = object([("ticket", ticket.pubkey().to_string().into())]);
+
+            result.extend(context.send(
+                &[buy_ticket(&keys, &options, &context.pubkey(), &ticket.pubkey(), args)],
+                &[&ticket],
+            )?);
+
+            if !context.dry_run {
+                let (first, last) = decode_ticket_range(&context.account(&ticket.pubkey())?.data)?;
+
+                result.insert("first".to_string(), first.into());
+                result.insert("last".to_string(), last.into());
+            }
+
+            Ok(result.into())
+        }
+        TicketCommand::Scratch { raffle, ticket: ticket_address, house } => {
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+            let options = context.raffle_options(raffle_config.payment_mint, raffle_config.prize)?;
+
+            // Scratched tickets are burned, so the ticket is read beforehand
+            let data = context.account(ticket_address)?.data;
+            let mut result = ticket_row(ticket_address, &data, &keys.raffle_config(), &raffle_config)?;
+
+            result.as_object_mut().unwrap().extend(context.send(
+                &[scratch_ticket(&keys, &options, &context.pubkey(), ticket_address)],
+                &[],
+            )?);
+
+            Ok(result)
+        }
+        TicketCommand::List { raffle, house, owner } => {
+            let (keys, raffle_config) = context.raffle(&context.house(house), raffle)?;
+
+            let mut update_authority = vec![UPDATE_AUTHORITY_COLLECTION];
+            update_authority.extend_from_slice(raffle.as_ref());
+
+            let mut filters = vec![
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![ASSET_V1_KEY])),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(UPDATE_AUTHORITY_OFFSET, update_authority)),
+            ];
+
+            if let Some(owner) = owner {
+                filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(OWNER_OFFSET, owner.to_bytes().to_vec())));
+            }
+
+            let mut tickets = vec![];
+
+            for (address, account) in context.backend.get_program_accounts(&mpl_core::ID, filters)? {
+                tickets.push(ticket_row(&address, &account.data, &keys.raffle_config(), &raffle_config)?);
+            }
+
+            tickets.sort_by_key(|ticket| ticket["first"].as_u64());
+
+            Ok(tickets.into())
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// Command-line tool for house operators and raffle makers, built on beeraf-client.
+// The commands go through a Backend so they run against an RPC node or an in-process bank.
+
+pub mod args;
+pub mod backend;
+pub mod commands;
+pub mod output;
+
+use anyhow::Result;
+use solana_sdk::signature::Keypair;
+
+use crate::{
+    args::{Args, Command},
+    backend::Backend,
+    commands::{house, raffle, ticket, Context},
+};
+
+// Runs a command and renders its result in the requested output format
+pub fn run(args: &Args, backend: &dyn Backend, keypair: &Keypair) -> Result<String> {
+    let context = Context {
+        backend,
+        keypair,
+        dry_run: args.dry_run,
+    };
+
+    let result = match &args.command {
+        Command::House(command) => house::run(&context, command)?,
+        Command::Raffle(command) => raffle::run(&context, command)?,
+        Command::Ticket(command) => ticket::run(&context, command)?,
+    };
+
+    Ok(output::render(&result, args.output))
+}
@@ -0,0 +1,20 @@
+use anyhow::{anyhow, Result};
+use beeraf_cli::{args::Args, backend::RpcBackend};
+use clap::Parser;
+use solana_sdk::signature::read_keypair_file;
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let path = match args.keypair.strip_prefix("~/") {
+        Some(path) => format!("{}/{}", std::env::var("HOME")?, path),
+        None => args.keypair.clone(),
+    };
+
+    let keypair = read_keypair_file(&path).map_err(|err| anyhow!("Failed to read keypair {path}: {err}"))?;
+    let backend = RpcBackend::new(args.url.clone());
+
+    println!("{}", beeraf_cli::run(&args, &backend, &keypair)?);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use serde_json::Value;
+
+use crate::args::OutputFormat;
+
+pub fn render(value: &Value, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+        OutputFormat::Table => table(value),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn rows(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| rows.iter().filter_map(|row| row.get(column)).map(String::len).max().unwrap_or(0))
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Objects print as key/value pairs and lists of objects as one row per item
+fn table(value: &Value) -> String {
+    match value {
+        Value::Object(object) => rows(
+            &object
+                .iter()
+                .map(|(key, value)| vec![key.clone(), cell(value)])
+                .collect::<Vec<_>>(),
+        ),
+        Value::Array(items) if items.is_empty() => "No results".to_string(),
+        Value::Array(items) => match &items[0] {
+            Value::Object(first) => {
+                let header: Vec<String> = first.keys().cloned().collect();
+
+                let mut table = vec![header.iter().map(|key| key.to_uppercase()).collect()];
+
+                table.extend(items.iter().map(|item| header.iter().map(|key| cell(&item[key])).collect()));
+
+                rows(&table)
+            }
+            _ => items.iter().map(cell).collect::<Vec<_>>().join("\n"),
+        },
+        value => cell(value),
+    }
+}
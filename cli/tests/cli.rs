@@ -0,0 +1,275 @@
+// Runs the CLI commands against an in-process bank, through the same Backend
+// the binary uses for RPC. Like the program tests, the beeraf and mpl-core
+// programs are loaded from target/deploy (`anchor build` and `./dump_program.sh`).
+
+use std::{cell::RefCell, collections::BTreeSet};
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use beeraf_cli::{args::Args, backend::Backend};
+use beeraf_client::ENTROPY_DELAY;
+use clap::Parser;
+use serde_json::Value;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    clock::Clock,
+    hash::Hash,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use tokio::runtime::Runtime;
+
+struct BankBackend {
+    runtime: Runtime,
+    context: RefCell<ProgramTestContext>,
+    // The bank can not list program accounts, so every account a transaction
+    // touched is kept and filtered instead
+    touched: RefCell<BTreeSet<Pubkey>>,
+}
+
+impl BankBackend {
+    fn start(wallets: &[&Keypair]) -> Self {
+        if std::env::var("SBF_OUT_DIR").is_err() && std::env::var("BPF_OUT_DIR").is_err() {
+            std::env::set_var(
+                "SBF_OUT_DIR",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy"),
+            );
+        }
+
+        let mut program_test = ProgramTest::new("beeraf", beeraf_client::ID, None);
+        program_test.add_program("mpl_core", mpl_core::ID, None);
+        program_test.prefer_bpf(true);
+
+        for wallet in wallets {
+            program_test.add_account(
+                wallet.pubkey(),
+                Account::new(100 * LAMPORTS_PER_SOL, 0, &system_program::ID),
+            );
+        }
+
+        let runtime = Runtime::new().unwrap();
+        let context = runtime.block_on(program_test.start_with_context());
+
+        Self {
+            runtime,
+            context: RefCell::new(context),
+            touched: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    fn warp_slots(&self, slots: u64) {
+        let slot = self.get_clock().unwrap().slot;
+
+        self.context.borrow_mut().warp_to_slot(slot + slots).unwrap();
+    }
+}
+
+impl Backend for BankBackend {
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        let mut context = self.context.borrow_mut();
+
+        Ok(self.runtime.block_on(context.banks_client.get_account(*address))?)
+    }
+
+    fn get_program_accounts(&self, program: &Pubkey, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Account)>> {
+        let mut accounts = vec![];
+
+        for address in self.touched.borrow().iter() {
+            if let Some(account) = self.get_account(address)? {
+                let shared = AccountSharedData::from(account.clone());
+
+                if account.owner == *program && filters.iter().all(|filter| filter.allows(&shared)) {
+                    accounts.push((*address, account));
+                }
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    fn get_clock(&self) -> Result<Clock> {
+        let mut context = self.context.borrow_mut();
+
+        Ok(self.runtime.block_on(context.banks_client.get_sysvar::<Clock>())?)
+    }
+
+    fn latest_blockhash(&self) -> Result<Hash> {
+        let mut context = self.context.borrow_mut();
+
+        Ok(self.runtime.block_on(context.banks_client.get_latest_blockhash())?)
+    }
+
+    fn send_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        self.touched
+            .borrow_mut()
+            .extend(transaction.message.account_keys.iter().copied());
+
+        let mut context = self.context.borrow_mut();
+
+        self.runtime
+            .block_on(context.banks_client.process_transaction(transaction.clone()))?;
+
+        Ok(transaction.signatures[0])
+    }
+}
+
+fn run(backend: &BankBackend, keypair: &Keypair, args: &[&str]) -> Result<String> {
+    let args = Args::try_parse_from(std::iter::once("beeraf").chain(args.iter().copied()))?;
+
+    beeraf_cli::run(&args, backend, keypair)
+}
+
+fn json(backend: &BankBackend, keypair: &Keypair, args: &[&str]) -> Value {
+    let mut args = args.to_vec();
+    args.extend(["--output", "json"]);
+
+    serde_json::from_str(&run(backend, keypair, &args).unwrap()).unwrap()
+}
+
+const SEED: &str = "0707070707070707070707070707070707070707070707070707070707070707";
+
+// A house with a raffle of the maker, returns the raffle
+fn setup(backend: &BankBackend, house: &Keypair, maker: &Keypair) -> String {
+    let house_key = house.pubkey().to_string();
+
+    json(backend, house, &["house", "init", "--fee", "100000000", "--house-fee-bps", "200"]);
+
+    let raffle = json(backend, maker, &[
+        "raffle", "create",
+        "--house", &house_key,
+        "--name", "Raffle",
+        "--uri", "https://example.com/raffle.json",
+        "--ticket-price", "10000000",
+        "--raffle-fee", "500",
+        "--capacity", "10",
+        "--slot-interval", "100",
+        "--seed", SEED,
+    ]);
+
+    assert_eq!(raffle["seed"], SEED);
+
+    raffle["raffle"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn raffle_flow() {
+    let house = Keypair::new();
+    let maker = Keypair::new();
+    let buyers = [Keypair::new(), Keypair::new()];
+
+    let backend = BankBackend::start(&[&house, &maker, &buyers[0], &buyers[1]]);
+    let raffle = setup(&backend, &house, &maker);
+    let house_key = house.pubkey().to_string();
+
+    let config = json(&backend, &house, &["house", "show"]);
+    assert_eq!(config["fee"], 100_000_000);
+    assert!(config["treasury_balance"].as_u64().unwrap() > 100_000_000);
+
+    let raffles = json(&backend, &house, &["raffle", "list"]);
+    assert_eq!(raffles.as_array().unwrap().len(), 1);
+    assert_eq!(raffles[0]["raffle"], raffle.as_str());
+    assert_eq!(raffles[0]["status"], "Open");
+
+    for (buyer, quantity) in buyers.iter().zip(["2", "3"]) {
+        json(&backend, buyer, &["ticket", "buy", &raffle, "--house", &house_key, "--quantity", quantity]);
+    }
+
+    let tickets = json(&backend, &house, &["ticket", "list", &raffle]);
+    assert_eq!(tickets.as_array().unwrap().len(), 2);
+    assert_eq!(tickets[1]["first"], 3);
+    assert_eq!(tickets[1]["last"], 5);
+
+    let owned = json(&backend, &house, &["ticket", "list", &raffle, "--owner", &buyers[0].pubkey().to_string()]);
+    assert_eq!(owned.as_array().unwrap().len(), 1);
+
+    backend.warp_slots(101);
+
+    let scheduled = json(&backend, &maker, &["raffle", "solve", &raffle, "--house", &house_key, "--seed", SEED]);
+    assert!(scheduled["entropy_slot"].as_u64().unwrap() > 0);
+
+    // The hash of the entropy slot is in SlotHashes once a later slot is produced
+    backend.warp_slots(ENTROPY_DELAY + 1);
+    backend.warp_slots(1);
+
+    let solved = json(&backend, &maker, &["raffle", "solve", &raffle, "--house", &house_key, "--seed", SEED]);
+    assert_eq!(solved["signatures"].as_array().unwrap().len(), 2);
+
+    let shown = json(&backend, &maker, &["raffle", "show", &raffle, "--house", &house_key]);
+    assert_eq!(shown["status"], "Resolved");
+    assert_eq!(shown["winners"], solved["winners"]);
+
+    let tickets = json(&backend, &house, &["ticket", "list", &raffle]);
+    let winning = tickets
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|ticket| ticket["places"] == serde_json::json!([1]))
+        .unwrap();
+    let winner = buyers
+        .iter()
+        .find(|buyer| winning["owner"] == buyer.pubkey().to_string())
+        .unwrap();
+
+    let scratched = json(&backend, winner, &[
+        "ticket", "scratch", &raffle, winning["ticket"].as_str().unwrap(), "--house", &house_key,
+    ]);
+    assert_eq!(scratched["places"], serde_json::json!([1]));
+
+    let shown = json(&backend, &maker, &["raffle", "show", &raffle, "--house", &house_key]);
+    assert_eq!(shown["status"], "Claimed");
+
+    json(&backend, &maker, &["raffle", "close", &raffle, "--house", &house_key]);
+
+    assert_eq!(json(&backend, &house, &["raffle", "list"]).as_array().unwrap().len(), 0);
+
+    let withdrawn = json(&backend, &house, &["house", "withdraw", "--amount", "100000000"]);
+    assert_eq!(withdrawn["amount"], 100_000_000);
+}
+
+#[test]
+fn dry_run_does_not_send() {
+    let house = Keypair::new();
+    let maker = Keypair::new();
+
+    let backend = BankBackend::start(&[&house, &maker]);
+    let raffle = setup(&backend, &house, &maker);
+
+    let buyer = Keypair::new();
+    let ticket = json(&backend, &buyer, &[
+        "ticket", "buy", &raffle, "--house", &house.pubkey().to_string(), "--dry-run",
+    ]);
+
+    let transaction: Transaction = bincode::deserialize(
+        &STANDARD.decode(ticket["transaction"].as_str().unwrap()).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(transaction.signatures.len(), 2);
+    assert!(transaction.verify().is_ok());
+
+    let tickets = json(&backend, &house, &["ticket", "list", &raffle]);
+    assert_eq!(tickets.as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn table_output() {
+    let house = Keypair::new();
+    let maker = Keypair::new();
+
+    let backend = BankBackend::start(&[&house, &maker]);
+    let raffle = setup(&backend, &house, &maker);
+
+    let table = run(&backend, &house, &["raffle", "list"]).unwrap();
+
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("RAFFLE"));
+    assert!(lines[1].starts_with(&raffle));
+
+    assert_eq!(run(&backend, &house, &["ticket", "list", &raffle]).unwrap(), "No results");
+}
@@ -0,0 +1,94 @@
+use anchor_lang::{
+    prelude::{AccountInfo, Pubkey},
+    AccountDeserialize, Result,
+};
+use anchor_spl::token_interface::TokenAccount;
+use beeraf::{error::BeeRafError, ticket_range, Config, RaffleConfig, TicketData};
+use mpl_core::{
+    accounts::BaseCollectionV1,
+    fetch_plugin,
+    types::{Attributes, PluginType},
+};
+
+pub fn decode_config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_raffle_config(data: &[u8]) -> Result<RaffleConfig> {
+    RaffleConfig::try_deserialize(&mut &data[..])
+}
+
+// Token account of the vault of a raffle paid with tokens (SPL Token or Token-2022),
+// the vault of a SOL raffle holds its pot as lamports and has no data
+pub fn decode_token_account(data: &[u8]) -> Result<TokenAccount> {
+    TokenAccount::try_deserialize(&mut &data[..])
+}
+
+// mpl-core reads plugins from an AccountInfo, so the fetched data gets wrapped in one
+fn with_account_info<T>(data: &[u8], read: impl FnOnce(&AccountInfo) -> Result<T>) -> Result<T> {
+    let key = Pubkey::default();
+    let mut lamports = 0;
+    let mut data = data.to_vec();
+
+    let account_info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &mpl_core::ID, false, 0);
+
+    read(&account_info)
+}
+
+// Attributes the program writes on the raffle collection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RaffleAttributes {
+    pub capacity: u32,
+    // Winning ticket numbers from the Winner, Winner 2, ... attributes (empty until resolved)
+    pub winners: Vec<u32>,
+}
+
+pub fn collection_attributes(data: &[u8]) -> Result<Vec<(String, String)>> {
+    with_account_info(data, |collection| {
+        let (_, attributes, _) = fetch_plugin::<BaseCollectionV1, Attributes>(
+            collection,
+            PluginType::Attributes,
+        )?;
+
+        Ok(attributes
+            .attribute_list
+            .into_iter()
+            .map(|attr| (attr.key, attr.value))
+            .collect())
+    })
+}
+
+pub fn decode_raffle_attributes(data: &[u8]) -> Result<RaffleAttributes> {
+    let attributes = collection_attributes(data)?;
+
+    let attribute = |key: &str| -> Result<Option<u32>> {
+        attributes
+            .iter()
+            .find(|(attr_key, _)| attr_key == key)
+            .map(|(_, value)| value.parse::<u32>().map_err(|_| BeeRafError::NumericalOverflow.into()))
+            .transpose()
+    };
+
+    let capacity = attribute("Capacity")?.ok_or(BeeRafError::MissingAttribute)?;
+
+    let mut winners = vec![];
+
+    while let Some(winner) = attribute(&match winners.len() {
+        0 => "Winner".to_string(),
+        place => format!("Winner {}", place + 1),
+    })? {
+        winners.push(winner);
+    }
+
+    Ok(RaffleAttributes { capacity, winners })
+}
+
+// First and last ticket numbers held by a ticket asset
+pub fn decode_ticket_range(data: &[u8]) -> Result<(u32, u32)> {
+    with_account_info(data, ticket_range)
+}
+
+// Ticket state the program keeps in the AppData plugin of the ticket
+pub fn decode_ticket_data(data: &[u8], raffle_config: &Pubkey) -> Result<TicketData> {
+    with_account_info(data, |ticket| TicketData::read(ticket, *raffle_config))
+}
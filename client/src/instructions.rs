@@ -0,0 +1,583 @@
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use beeraf::{accounts, instruction, BuyTicketArgs, CreateRaffleArgs, Prize, RaffleConfig, RafflePolicy, UpdateConfigArgs};
+use solana_sdk::{
+    ed25519_program,
+    signature::{Keypair, Signature, Signer},
+};
+
+use crate::pda::{find_config, find_event_authority, find_legacy_vault, find_raffle_config, find_treasury, find_vault};
+
+// Keys every raffle instruction is built from, the other accounts are derived from them
+#[derive(Clone, Copy, Debug)]
+pub struct RaffleKeys {
+    pub house: Pubkey,
+    pub maker: Pubkey,
+    // mpl-core collection holding the tickets
+    pub raffle: Pubkey,
+}
+
+impl RaffleKeys {
+    pub fn treasury(&self) -> Pubkey {
+        find_treasury(&self.house).0
+    }
+
+    pub fn config(&self) -> Pubkey {
+        find_config(&self.house).0
+    }
+
+    pub fn raffle_config(&self) -> Pubkey {
+        find_raffle_config(&self.house, &self.raffle).0
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        find_vault(&self.raffle).0
+    }
+}
+
+// Mint and token program of a raffle paid with tokens. The builders use the
+// associated token accounts of every party, which must exist beforehand
+// (the vault one is created by create_raffle).
+#[derive(Clone, Copy, Debug)]
+pub struct Payment {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl Payment {
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program)
+    }
+}
+
+// Escrowed prize of a raffle, with the collection of an asset prize
+// or the token program of a token prize
+#[derive(Clone, Copy)]
+pub struct EscrowedPrize {
+    pub prize: Prize,
+    pub collection: Option<Pubkey>,
+    pub token_program: Pubkey,
+}
+
+// Extra accounts of raffles paid with tokens or with an escrowed prize,
+// the default is a SOL raffle without prize
+#[derive(Clone, Copy, Default)]
+pub struct RaffleOptions {
+    pub payment: Option<Payment>,
+    pub prize: Option<EscrowedPrize>,
+}
+
+// Prize accounts as the program expects them, `from` and `to` own the token accounts
+struct PrizeKeys {
+    asset: Option<Pubkey>,
+    collection: Option<Pubkey>,
+    mint: Option<Pubkey>,
+    source_token_account: Option<Pubkey>,
+    destination_token_account: Option<Pubkey>,
+    token_program: Option<Pubkey>,
+    associated_token_program: Option<Pubkey>,
+}
+
+impl RaffleOptions {
+    fn prize_keys(&self, from: &Pubkey, to: &Pubkey) -> PrizeKeys {
+        let mut keys = PrizeKeys {
+            asset: None,
+            collection: None,
+            mint: None,
+            source_token_account: None,
+            destination_token_account: None,
+            token_program: None,
+            associated_token_program: None,
+        };
+
+        match self.prize {
+            Some(EscrowedPrize { prize: Prize::Asset { asset }, collection, .. }) => {
+                keys.asset = Some(asset);
+                keys.collection = collection;
+            }
+            Some(EscrowedPrize { prize: Prize::Token { mint, .. }, token_program, .. }) => {
+                keys.mint = Some(mint);
+                keys.source_token_account = Some(get_associated_token_address_with_program_id(from, &mint, &token_program));
+                keys.destination_token_account = Some(get_associated_token_address_with_program_id(to, &mint, &token_program));
+                keys.token_program = Some(token_program);
+                keys.associated_token_program = Some(associated_token::ID);
+            }
+            _ => {}
+        }
+
+        keys
+    }
+
+    fn token_account(&self, owner: &Pubkey) -> Option<Pubkey> {
+        self.payment.map(|payment| payment.token_account(owner))
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: beeraf::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(house: &Pubkey, fee: u64, house_fee_bps: u16, policy: RafflePolicy) -> Instruction {
+    build(
+        accounts::Initialize {
+            house: *house,
+            treasury: find_treasury(house).0,
+            config: find_config(house).0,
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::Initialize { fee, house_fee_bps, policy },
+    )
+}
+
+// The raffle keypair signs along with the maker
+pub fn create_raffle(keys: &RaffleKeys, options: &RaffleOptions, args: CreateRaffleArgs) -> Instruction {
+    let prize = options.prize_keys(&keys.maker, &keys.vault());
+
+    build(
+        accounts::CreateRaffle {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            vault: keys.vault(),
+            payment_mint: options.payment.map(|payment| payment.mint),
+            vault_token_account: options.token_account(&keys.vault()),
+            treasury_token_account: options.token_account(&keys.treasury()),
+            prize_asset: prize.asset,
+            prize_collection: prize.collection,
+            prize_mint: prize.mint,
+            maker_prize_token_account: prize.source_token_account,
+            vault_prize_token_account: prize.destination_token_account,
+            prize_token_program: prize.token_program,
+            mpl_core_program: mpl_core::ID,
+            token_program: options.payment.map(|payment| payment.token_program),
+            associated_token_program: (options.payment.is_some() || prize.associated_token_program.is_some())
+                .then_some(associated_token::ID),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::CreateRaffle { args },
+    )
+}
+
+// The ticket keypair signs along with the buyer
+pub fn buy_ticket(keys: &RaffleKeys, options: &RaffleOptions, buyer: &Pubkey, ticket: &Pubkey, args: BuyTicketArgs) -> Instruction {
+    build(
+        accounts::BuyTicket {
+            buyer: *buyer,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            ticket: *ticket,
+            vault: keys.vault(),
+            payment_mint: options.payment.map(|payment| payment.mint),
+            buyer_token_account: options.token_account(buyer),
+            vault_token_account: options.token_account(&keys.vault()),
+            mpl_core_program: mpl_core::ID,
+            token_program: options.payment.map(|payment| payment.token_program),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::BuyTicket { args },
+    )
+}
+
+// The maker of a legacy raffle commits the hash of its seed before the entropy is captured
+pub fn commit_seed(keys: &RaffleKeys, commitment: [u8; 32]) -> Instruction {
+    build(
+        accounts::CommitSeed {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::CommitSeed { commitment },
+    )
+}
+
+// Anyone can capture the entropy once ticket sales are over: the first call schedules
+// the entropy slot, the second captures its hash once the slot has been produced
+pub fn capture_entropy(keys: &RaffleKeys, signer: &Pubkey) -> Instruction {
+    build(
+        accounts::CaptureEntropy {
+            signer: *signer,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            slot_hashes: sysvar::slot_hashes::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::CaptureEntropy {},
+    )
+}
+
+fn solve_raffle_accounts(keys: &RaffleKeys, options: &RaffleOptions, randomness: Option<Pubkey>) -> accounts::SolveRaffle {
+    accounts::SolveRaffle {
+        maker: keys.maker,
+        house: keys.house,
+        treasury: keys.treasury(),
+        config: keys.config(),
+        raffle: keys.raffle,
+        raffle_config: keys.raffle_config(),
+        vault: keys.vault(),
+        mpl_core_program: mpl_core::ID,
+        instruction_sysvar: sysvar::instructions::ID,
+        randomness,
+        payment_mint: options.payment.map(|payment| payment.mint),
+        maker_token_account: options.token_account(&keys.maker),
+        vault_token_account: options.token_account(&keys.vault()),
+        treasury_token_account: options.token_account(&keys.treasury()),
+        token_program: options.payment.map(|payment| payment.token_program),
+        system_program: system_program::ID,
+        event_authority: find_event_authority().0,
+        program: beeraf::ID,
+    }
+}
+
+// Ed25519 precompile instruction for a single signature, with the public key,
+// signature and message inline after the offsets
+pub fn ed25519_instruction(public_key: &Pubkey, signature: &Signature, message: &[u8]) -> Instruction {
+    const DATA_START: u16 = 2 + 14;
+
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_data_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_data_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    data.extend_from_slice(public_key.as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+// The maker signs the raffle details off-chain, `solve_raffle` checks that signature
+// in the Ed25519 instruction, which must be the first one of the transaction
+pub fn solve_raffle(
+    keys: &RaffleKeys,
+    options: &RaffleOptions,
+    raffle_config: &RaffleConfig,
+    signature: &Signature,
+    seed: [u8; 32],
+) -> [Instruction; 2] {
+    [
+        ed25519_instruction(&keys.maker, signature, &raffle_config.to_slice()),
+        build(
+            solve_raffle_accounts(keys, options, None),
+            instruction::SolveRaffle {
+                sig: signature.as_ref().to_vec(),
+                seed,
+            },
+        ),
+    ]
+}
+
+// Same as `solve_raffle`, signing the raffle details with the maker keypair
+pub fn solve_raffle_signed(
+    keys: &RaffleKeys,
+    options: &RaffleOptions,
+    raffle_config: &RaffleConfig,
+    maker: &Keypair,
+    seed: [u8; 32],
+) -> [Instruction; 2] {
+    let signature = maker.sign_message(&raffle_config.to_slice());
+
+    solve_raffle(keys, options, raffle_config, &signature, seed)
+}
+
+#[cfg(feature = "vrf")]
+pub fn request_randomness(keys: &RaffleKeys, randomness: &Pubkey) -> Instruction {
+    build(
+        accounts::RequestRandomness {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            randomness: *randomness,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::RequestRandomness {},
+    )
+}
+
+#[cfg(feature = "vrf")]
+pub fn solve_raffle_vrf(keys: &RaffleKeys, options: &RaffleOptions, randomness: &Pubkey) -> Instruction {
+    build(
+        solve_raffle_accounts(keys, options, Some(*randomness)),
+        instruction::SolveRaffleVrf {},
+    )
+}
+
+pub fn refund_ticket(keys: &RaffleKeys, options: &RaffleOptions, buyer: &Pubkey, ticket: &Pubkey) -> Instruction {
+    build(
+        accounts::RefundTicket {
+            buyer: *buyer,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle_config: keys.raffle_config(),
+            raffle: keys.raffle,
+            ticket: *ticket,
+            vault: keys.vault(),
+            payment_mint: options.payment.map(|payment| payment.mint),
+            buyer_token_account: options.token_account(buyer),
+            vault_token_account: options.token_account(&keys.vault()),
+            mpl_core_program: mpl_core::ID,
+            token_program: options.payment.map(|payment| payment.token_program),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::RefundTicket {},
+    )
+}
+
+pub fn scratch_ticket(keys: &RaffleKeys, options: &RaffleOptions, buyer: &Pubkey, ticket: &Pubkey) -> Instruction {
+    let prize = options.prize_keys(&keys.vault(), buyer);
+
+    build(
+        accounts::ScratchTicket {
+            buyer: *buyer,
+            house: keys.house,
+            maker: keys.maker,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle_config: keys.raffle_config(),
+            raffle: keys.raffle,
+            ticket: *ticket,
+            vault: keys.vault(),
+            payment_mint: options.payment.map(|payment| payment.mint),
+            buyer_token_account: options.token_account(buyer),
+            vault_token_account: options.token_account(&keys.vault()),
+            prize_asset: prize.asset,
+            prize_collection: prize.collection,
+            prize_mint: prize.mint,
+            vault_prize_token_account: prize.source_token_account,
+            buyer_prize_token_account: prize.destination_token_account,
+            prize_token_program: prize.token_program,
+            associated_token_program: prize.associated_token_program,
+            mpl_core_program: mpl_core::ID,
+            token_program: options.payment.map(|payment| payment.token_program),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::ScratchTicket {},
+    )
+}
+
+pub fn cancel_raffle(keys: &RaffleKeys, options: &RaffleOptions) -> Instruction {
+    let prize = options.prize_keys(&keys.vault(), &keys.maker);
+
+    build(
+        accounts::CancelRaffle {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            vault: keys.vault(),
+            prize_asset: prize.asset,
+            prize_collection: prize.collection,
+            prize_mint: prize.mint,
+            vault_prize_token_account: prize.source_token_account,
+            maker_prize_token_account: prize.destination_token_account,
+            mpl_core_program: mpl_core::ID,
+            prize_token_program: prize.token_program,
+            associated_token_program: prize.associated_token_program,
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::CancelRaffle {},
+    )
+}
+
+pub fn reclaim_prize(keys: &RaffleKeys, options: &RaffleOptions) -> Instruction {
+    let prize = options.prize_keys(&keys.vault(), &keys.maker);
+
+    build(
+        accounts::ReclaimPrize {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            vault: keys.vault(),
+            prize_asset: prize.asset,
+            prize_collection: prize.collection,
+            prize_mint: prize.mint,
+            vault_prize_token_account: prize.source_token_account,
+            maker_prize_token_account: prize.destination_token_account,
+            mpl_core_program: mpl_core::ID,
+            prize_token_program: prize.token_program,
+            associated_token_program: prize.associated_token_program,
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::ReclaimPrize {},
+    )
+}
+
+pub fn close_raffle(keys: &RaffleKeys, options: &RaffleOptions) -> Instruction {
+    build(
+        accounts::CloseRaffle {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            vault: keys.vault(),
+            payment_mint: options.payment.map(|payment| payment.mint),
+            maker_token_account: options.token_account(&keys.maker),
+            vault_token_account: options.token_account(&keys.vault()),
+            treasury_token_account: options.token_account(&keys.treasury()),
+            mpl_core_program: mpl_core::ID,
+            token_program: options.payment.map(|payment| payment.token_program),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::CloseRaffle {},
+    )
+}
+
+// With a payment, it withdraws the tokens of the treasury token account instead of lamports
+pub fn withdraw_treasury(
+    house: &Pubkey,
+    authority: &Pubkey,
+    destination: &Pubkey,
+    payment: Option<&Payment>,
+    amount: u64,
+) -> Instruction {
+    let treasury = find_treasury(house).0;
+
+    build(
+        accounts::WithdrawTreasury {
+            authority: *authority,
+            house: *house,
+            treasury,
+            config: find_config(house).0,
+            destination: *destination,
+            mint: payment.map(|payment| payment.mint),
+            treasury_token_account: payment.map(|payment| payment.token_account(&treasury)),
+            destination_token_account: payment.map(|payment| payment.token_account(destination)),
+            token_program: payment.map(|payment| payment.token_program),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::WithdrawTreasury { amount },
+    )
+}
+
+pub fn update_config(house: &Pubkey, authority: &Pubkey, args: UpdateConfigArgs) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            authority: *authority,
+            house: *house,
+            treasury: find_treasury(house).0,
+            config: find_config(house).0,
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::UpdateConfig { args },
+    )
+}
+
+pub fn propose_authority(house: &Pubkey, authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        accounts::ProposeAuthority {
+            authority: *authority,
+            house: *house,
+            treasury: find_treasury(house).0,
+            config: find_config(house).0,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::ProposeAuthority {
+            new_authority: *new_authority,
+        },
+    )
+}
+
+pub fn accept_authority(house: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        accounts::AcceptAuthority {
+            new_authority: *new_authority,
+            house: *house,
+            treasury: find_treasury(house).0,
+            config: find_config(house).0,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::AcceptAuthority {},
+    )
+}
+
+pub fn migrate_vault(keys: &RaffleKeys) -> Instruction {
+    build(
+        accounts::MigrateVault {
+            maker: keys.maker,
+            house: keys.house,
+            treasury: keys.treasury(),
+            config: keys.config(),
+            raffle: keys.raffle,
+            raffle_config: keys.raffle_config(),
+            legacy_vault: find_legacy_vault(&keys.maker).0,
+            vault: keys.vault(),
+            system_program: system_program::ID,
+            event_authority: find_event_authority().0,
+            program: beeraf::ID,
+        },
+        instruction::MigrateVault {},
+    )
+}
@@ -0,0 +1,16 @@
+// Rust client of the beeraf program: PDA finders, instruction builders for every
+// program function and decoders for the accounts and mpl-core attributes it writes.
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use accounts::*;
+pub use instructions::*;
+pub use pda::*;
+
+pub use beeraf::{
+    error::BeeRafError, BuyTicketArgs, Config, CreateRaffleArgs, Prize, RaffleConfig, RaffleEnd,
+    RafflePolicy, RaffleStatus, RandomnessSource, TicketData, UpdateConfigArgs, ENTROPY_DELAY, ID,
+    SLOT_HASHES_WINDOW,
+};
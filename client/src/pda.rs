@@ -0,0 +1,31 @@
+use anchor_lang::prelude::Pubkey;
+
+// Treasury of a house, it receives the raffle fees and the house cut of every ticket
+pub fn find_treasury(house: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury", house.as_ref()], &beeraf::ID)
+}
+
+pub fn find_config(house: &Pubkey) -> (Pubkey, u8) {
+    let (treasury, _) = find_treasury(house);
+
+    Pubkey::find_program_address(&[b"config", treasury.as_ref()], &beeraf::ID)
+}
+
+// RaffleConfig of a raffle, the raffle being the mpl-core collection holding the tickets
+pub fn find_raffle_config(house: &Pubkey, raffle: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"raffle", house.as_ref(), raffle.as_ref()], &beeraf::ID)
+}
+
+pub fn find_vault(raffle: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", raffle.as_ref()], &beeraf::ID)
+}
+
+// Vault seeded by the maker, used by raffles created before the per-raffle vault
+pub fn find_legacy_vault(maker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", maker.as_ref()], &beeraf::ID)
+}
+
+// Signer of the self-CPI used by emit_cpi!
+pub fn find_event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"__event_authority"], &beeraf::ID)
+}
@@ -0,0 +1,280 @@
+// Checks the accounts the client derives against the account constraints of the program,
+// the decoders against serialized accounts, and the Ed25519 instruction against the parser
+// the program uses, without running the program.
+
+use std::collections::BTreeSet;
+
+use anchor_instruction_sysvar::Ed25519InstructionSignatures;
+use anchor_lang::{error::ErrorCode, prelude::AccountInfo, system_program, AccountSerialize, AnchorSerialize, Accounts};
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenState, AccountState},
+};
+use beeraf::{MigrateVault, MigrateVaultBumps};
+use beeraf_client::{
+    decode_config, decode_raffle_config, decode_token_account, ed25519_instruction, find_config,
+    find_event_authority, find_legacy_vault, find_raffle_config, find_treasury, find_vault, migrate_vault, Config,
+    Payment, Prize, RaffleConfig, RafflePolicy, RaffleKeys, RaffleStatus, RandomnessSource, ID,
+};
+use mpl_core::{accounts::BaseCollectionV1, types::Key};
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    ed25519_instruction::verify,
+    ed25519_program,
+    feature_set::FeatureSet,
+    instruction::Instruction,
+    native_loader,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+fn raffle_keys() -> RaffleKeys {
+    RaffleKeys {
+        house: Pubkey::new_unique(),
+        maker: Pubkey::new_unique(),
+        raffle: Pubkey::new_unique(),
+    }
+}
+
+fn config(house: &Pubkey) -> Config {
+    Config {
+        authority: Pubkey::new_unique(),
+        fee: 100_000,
+        config_bump: find_config(house).1,
+        treasury_bump: find_treasury(house).1,
+        pending_authority: Some(Pubkey::new_unique()),
+        house_fee_bps: 200,
+        policy: RafflePolicy {
+            max_ticket_price: Some(5_000_000),
+            cancel_refund_bps: Some(5_000),
+            ..Default::default()
+        },
+    }
+}
+
+// Runs the account constraints of migrate_vault on the accounts of the instruction. It takes
+// every PDA of a raffle (treasury, config, raffle config, both vaults and the event authority)
+// and checks their seeds with the program's own derivation
+fn migrate_vault_constraints(keys: &RaffleKeys, instruction: &Instruction) -> anchor_lang::Result<MigrateVaultBumps> {
+    let mut config_data = vec![];
+    config(&keys.house).try_serialize(&mut config_data)?;
+
+    let collection = BaseCollectionV1 {
+        key: Key::CollectionV1,
+        update_authority: keys.raffle_config(),
+        name: "Raffle".to_string(),
+        uri: "https://example.com/raffle.json".to_string(),
+        num_minted: 0,
+        current_size: 0,
+    };
+
+    let (owners, mut data): (Vec<Pubkey>, Vec<Vec<u8>>) = instruction
+        .accounts
+        .iter()
+        .map(|meta| match meta.pubkey {
+            key if key == keys.config() => (ID, config_data.clone()),
+            key if key == keys.raffle => (mpl_core::ID, collection.try_to_vec().unwrap()),
+            key if key == keys.raffle_config() => (ID, vec![]),
+            key if key == system_program::ID => (native_loader::ID, vec![]),
+            key if key == ID => (bpf_loader_upgradeable::ID, vec![]),
+            _ => (system_program::ID, vec![]),
+        })
+        .unzip();
+    let mut lamports = vec![1_000_000_000; owners.len()];
+
+    let infos: Vec<AccountInfo> = instruction
+        .accounts
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .zip(&owners)
+        .map(|(((meta, lamports), data), owner)| {
+            let executable = meta.pubkey == system_program::ID || meta.pubkey == ID;
+
+            AccountInfo::new(&meta.pubkey, meta.is_signer, meta.is_writable, lamports, data, owner, executable, 0)
+        })
+        .collect();
+
+    let mut bumps = MigrateVaultBumps::default();
+    MigrateVault::try_accounts(&ID, &mut &infos[..], &[], &mut bumps, &mut BTreeSet::new())?;
+
+    Ok(bumps)
+}
+
+#[test]
+fn builders_pass_the_program_seeds() {
+    let keys = raffle_keys();
+    let instruction = migrate_vault(&keys);
+
+    let bumps = migrate_vault_constraints(&keys, &instruction).unwrap();
+
+    assert_eq!(bumps.raffle_config, find_raffle_config(&keys.house, &keys.raffle).1);
+    assert_eq!(bumps.legacy_vault, find_legacy_vault(&keys.maker).1);
+    assert_eq!(bumps.vault, find_vault(&keys.raffle).1);
+    assert_eq!(bumps.event_authority, find_event_authority().1);
+
+    // The constraints catch an account derived from the wrong seeds
+    let mut swapped = instruction.clone();
+    let legacy_vault = swapped.accounts.iter().position(|meta| meta.pubkey == find_legacy_vault(&keys.maker).0);
+    let vault = swapped.accounts.iter().position(|meta| meta.pubkey == keys.vault());
+    swapped.accounts.swap(legacy_vault.unwrap(), vault.unwrap());
+
+    assert_eq!(
+        migrate_vault_constraints(&keys, &swapped).unwrap_err(),
+        ErrorCode::ConstraintSeeds.into()
+    );
+
+    // Another house gets its own raffle config for the same collection
+    let other_house = Pubkey::new_unique();
+    assert_ne!(find_raffle_config(&other_house, &keys.raffle).0, keys.raffle_config());
+}
+
+#[test]
+fn decodes_a_serialized_config() {
+    let house = Pubkey::new_unique();
+    let config = config(&house);
+
+    let mut data = vec![];
+    config.try_serialize(&mut data).unwrap();
+
+    let decoded = decode_config(&data).unwrap();
+    assert_eq!((decoded.authority, decoded.pending_authority), (config.authority, config.pending_authority));
+    assert_eq!((decoded.fee, decoded.house_fee_bps), (100_000, 200));
+    assert_eq!((decoded.config_bump, decoded.treasury_bump), (config.config_bump, config.treasury_bump));
+    assert_eq!(decoded.policy.max_ticket_price, Some(5_000_000));
+    assert_eq!(decoded.policy.cancel_refund_bps, Some(5_000));
+    assert_eq!(decoded.policy.min_ticket_price, None);
+
+    let mut encoded = vec![];
+    decoded.try_serialize(&mut encoded).unwrap();
+    assert_eq!(encoded, data);
+
+    // Another account type is rejected by its discriminator
+    assert!(decode_raffle_config(&data).is_err());
+}
+
+#[test]
+fn decodes_a_serialized_raffle_config() {
+    let keys = raffle_keys();
+    let payment_mint = Pubkey::new_unique();
+
+    let raffle_config = RaffleConfig {
+        authority: keys.maker,
+        collection: keys.raffle,
+        slot: 1_050,
+        ticket_price: 1_000,
+        raffle_fee: 500,
+        raffle_config_bump: find_raffle_config(&keys.house, &keys.raffle).1,
+        vault_bump: find_vault(&keys.raffle).1,
+        pot: 4_650,
+        vault_version: beeraf::VAULT_VERSION,
+        capacity: 10,
+        min_tickets: 2,
+        escrowed_fees: 0,
+        resolved_slot: 1_070,
+        resolved_timestamp: 1_700_000_000,
+        commitment: [1; 32],
+        entropy: [2; 32],
+        entropy_slot: 1_060,
+        randomness_source: RandomnessSource::CommitReveal,
+        randomness_account: Pubkey::default(),
+        prize_pool: 4_650,
+        claimed_places: 0b01,
+        prize_tiers: vec![7_000, 3_000],
+        winners: vec![3, 8],
+        payment_mint: Some(payment_mint),
+        refunded_tickets: 0,
+        prize: Prize::Token { mint: Pubkey::new_unique(), amount: 1 },
+        prize_released: true,
+        tickets_sold: 9,
+        house_fee_bps: 200,
+        escrowed_house_fees: 0,
+        end_timestamp: 0,
+        start_timestamp: 0,
+        end_on_sell_out: false,
+        creation_fee: 100_000,
+        status: RaffleStatus::Resolved,
+        version: beeraf::RAFFLE_CONFIG_VERSION,
+    };
+
+    let mut data = vec![];
+    raffle_config.try_serialize(&mut data).unwrap();
+
+    let decoded = decode_raffle_config(&data).unwrap();
+    assert_eq!((decoded.authority, decoded.collection), (keys.maker, keys.raffle));
+    assert_eq!((decoded.pot, decoded.prize_pool, decoded.tickets_sold), (4_650, 4_650, 9));
+    assert_eq!(decoded.prize_tiers, [7_000, 3_000]);
+    assert_eq!(decoded.winners, [3, 8]);
+    assert_eq!(decoded.payment_mint, Some(payment_mint));
+    assert!(decoded.prize == raffle_config.prize && decoded.prize_released);
+    assert_eq!(decoded.status, RaffleStatus::Resolved);
+    assert_eq!((decoded.resolved_slot, decoded.resolved_timestamp), (1_070, 1_700_000_000));
+
+    let mut encoded = vec![];
+    decoded.try_serialize(&mut encoded).unwrap();
+    assert_eq!(encoded, data);
+
+    // The account is allocated with room to spare, the padding is ignored
+    data.resize(RaffleConfig::INIT_SPACE, 0);
+    assert_eq!(decode_raffle_config(&data).unwrap().winners, [3, 8]);
+
+    assert!(decode_config(&data).is_err());
+}
+
+#[test]
+fn decodes_the_vault_token_account() {
+    let keys = raffle_keys();
+    let payment = Payment { mint: Pubkey::new_unique(), token_program: spl_token::ID };
+
+    let mut data = [0; TokenState::LEN];
+    TokenState {
+        mint: payment.mint,
+        owner: keys.vault(),
+        amount: 4_650,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+
+    let vault = decode_token_account(&data).unwrap();
+    assert_eq!((vault.mint, vault.owner, vault.amount), (payment.mint, keys.vault(), 4_650));
+
+    // The builders pass the associated token account of the vault
+    assert_eq!(
+        payment.token_account(&keys.vault()),
+        Pubkey::find_program_address(
+            &[keys.vault().as_ref(), spl_token::ID.as_ref(), payment.mint.as_ref()],
+            &anchor_spl::associated_token::ID,
+        )
+        .0
+    );
+
+    // An uninitialized account is rejected
+    assert!(decode_token_account(&[0; TokenState::LEN]).is_err());
+}
+
+#[test]
+fn ed25519_instruction_is_read_by_the_program() {
+    let maker = Keypair::new();
+    let message = b"raffle details";
+    let signature = maker.sign_message(message);
+
+    let instruction = ed25519_instruction(&maker.pubkey(), &signature, message);
+
+    assert_eq!(instruction.program_id, ed25519_program::ID);
+    assert!(instruction.accounts.is_empty());
+
+    // The offsets are parsed like verify_ed25519_signature does
+    let signatures = Ed25519InstructionSignatures::unpack(&instruction.data).unwrap().0;
+    assert_eq!(signatures.len(), 1);
+
+    let parsed = &signatures[0];
+    assert!(parsed.is_verifiable);
+    assert_eq!(parsed.public_key, Some(maker.pubkey()));
+    assert_eq!(&parsed.signature.as_ref().unwrap()[..], signature.as_ref());
+    assert_eq!(parsed.message.as_deref(), Some(&message[..]));
+
+    // and the precompile accepts the signature
+    verify(&instruction.data, &[&instruction.data], &FeatureSet::all_enabled()).unwrap();
+}
@@ -0,0 +1,60 @@
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use beeraf::{
+    AuthorityAccepted, AuthorityProposed, ConfigUpdated, EntropyCaptured, HouseInitialized, PrizeClaimed, PrizeReclaimed,
+    RaffleCancelled, RaffleClosed, RaffleCreated, RaffleMigrated, RaffleResolved, RandomnessRequested, SeedCommitted,
+    TicketPurchased, TicketRefunded, TreasuryWithdrawn,
+};
+
+// Events of the beeraf program the indexer replays
+pub enum BeeRafEvent {
+    HouseInitialized(HouseInitialized),
+    RaffleCreated(RaffleCreated),
+    TicketPurchased(TicketPurchased),
+    RaffleResolved(RaffleResolved),
+    PrizeClaimed(PrizeClaimed),
+    RaffleClosed(RaffleClosed),
+    TreasuryWithdrawn(TreasuryWithdrawn),
+    EntropyCaptured(EntropyCaptured),
+    RandomnessRequested(RandomnessRequested),
+    RaffleCancelled(RaffleCancelled),
+    TicketRefunded(TicketRefunded),
+    PrizeReclaimed(PrizeReclaimed),
+    ConfigUpdated(ConfigUpdated),
+    AuthorityProposed(AuthorityProposed),
+    AuthorityAccepted(AuthorityAccepted),
+    SeedCommitted(SeedCommitted),
+    RaffleMigrated(RaffleMigrated),
+}
+
+impl BeeRafEvent {
+    // Event data is the event discriminator followed by the borsh encoded event,
+    // None for events of other programs or layouts this indexer does not know
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        let (discriminator, mut event) = data.split_at(8);
+
+        match discriminator {
+            d if d == HouseInitialized::DISCRIMINATOR => HouseInitialized::deserialize(&mut event).ok().map(Self::HouseInitialized),
+            d if d == RaffleCreated::DISCRIMINATOR => RaffleCreated::deserialize(&mut event).ok().map(Self::RaffleCreated),
+            d if d == TicketPurchased::DISCRIMINATOR => TicketPurchased::deserialize(&mut event).ok().map(Self::TicketPurchased),
+            d if d == RaffleResolved::DISCRIMINATOR => RaffleResolved::deserialize(&mut event).ok().map(Self::RaffleResolved),
+            d if d == PrizeClaimed::DISCRIMINATOR => PrizeClaimed::deserialize(&mut event).ok().map(Self::PrizeClaimed),
+            d if d == RaffleClosed::DISCRIMINATOR => RaffleClosed::deserialize(&mut event).ok().map(Self::RaffleClosed),
+            d if d == TreasuryWithdrawn::DISCRIMINATOR => TreasuryWithdrawn::deserialize(&mut event).ok().map(Self::TreasuryWithdrawn),
+            d if d == EntropyCaptured::DISCRIMINATOR => EntropyCaptured::deserialize(&mut event).ok().map(Self::EntropyCaptured),
+            d if d == RandomnessRequested::DISCRIMINATOR => RandomnessRequested::deserialize(&mut event).ok().map(Self::RandomnessRequested),
+            d if d == RaffleCancelled::DISCRIMINATOR => RaffleCancelled::deserialize(&mut event).ok().map(Self::RaffleCancelled),
+            d if d == TicketRefunded::DISCRIMINATOR => TicketRefunded::deserialize(&mut event).ok().map(Self::TicketRefunded),
+            d if d == PrizeReclaimed::DISCRIMINATOR => PrizeReclaimed::deserialize(&mut event).ok().map(Self::PrizeReclaimed),
+            d if d == ConfigUpdated::DISCRIMINATOR => ConfigUpdated::deserialize(&mut event).ok().map(Self::ConfigUpdated),
+            d if d == AuthorityProposed::DISCRIMINATOR => AuthorityProposed::deserialize(&mut event).ok().map(Self::AuthorityProposed),
+            d if d == AuthorityAccepted::DISCRIMINATOR => AuthorityAccepted::deserialize(&mut event).ok().map(Self::AuthorityAccepted),
+            d if d == SeedCommitted::DISCRIMINATOR => SeedCommitted::deserialize(&mut event).ok().map(Self::SeedCommitted),
+            d if d == RaffleMigrated::DISCRIMINATOR => RaffleMigrated::deserialize(&mut event).ok().map(Self::RaffleMigrated),
+            _ => None,
+        }
+    }
+}
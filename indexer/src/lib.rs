@@ -0,0 +1,11 @@
+// Off-chain indexer of the beeraf program: it decodes the events of confirmed
+// transactions (sent through the emit_cpi! self-CPI or logged with emit!),
+// replays them into SQLite and answers history queries.
+
+pub mod events;
+pub mod parser;
+pub mod store;
+
+pub use events::BeeRafEvent;
+pub use parser::{parse_transaction, parse_transactions, TransactionEvents};
+pub use store::{HouseRow, Period, RevenueRow, Store, TicketRow, WinningsRow};
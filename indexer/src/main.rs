@@ -0,0 +1,163 @@
+use std::io::Read;
+
+use anyhow::Result;
+use beeraf_indexer::{parse_transactions, Period, Store};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "beeraf-indexer", about = "Replay beeraf events into SQLite and query the raffle history")]
+struct Args {
+    #[arg(long, default_value = "beeraf.db")]
+    db: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Replay transactions from getTransaction JSON files (stdin when none is given)")]
+    Ingest { files: Vec<String> },
+    #[command(about = "Current config and authority of a house")]
+    House { house: String },
+    #[command(about = "Tickets bought in a raffle")]
+    Tickets { raffle: String },
+    #[command(about = "Prizes paid per wallet")]
+    Winnings {
+        #[arg(long)]
+        wallet: Option<String>,
+    },
+    #[command(about = "Creation fees, house fees, sweeps and withdrawals of the treasury per period")]
+    Revenue {
+        #[arg(long)]
+        house: Option<String>,
+        #[arg(long, value_enum, default_value_t = Period::Month)]
+        period: Period,
+    },
+}
+
+fn print(header: &[&str], rows: Vec<Vec<String>>) {
+    let mut table = vec![header.iter().map(|column| column.to_string()).collect::<Vec<_>>()];
+    table.extend(rows);
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| table.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+
+    for row in table {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:width$}"))
+            .collect();
+
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+fn mint(mint: Option<String>) -> String {
+    mint.unwrap_or_else(|| "SOL".to_string())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut store = Store::open(&args.db)?;
+
+    match args.command {
+        Command::Ingest { files } => {
+            let mut inputs = vec![];
+
+            match files.is_empty() {
+                true => {
+                    let mut input = String::new();
+                    std::io::stdin().read_to_string(&mut input)?;
+                    inputs.push(input);
+                }
+                false => {
+                    for file in files {
+                        inputs.push(std::fs::read_to_string(file)?);
+                    }
+                }
+            }
+
+            let (mut replayed, mut skipped, mut truncated) = (0, 0, 0);
+
+            for input in inputs {
+                for transaction in parse_transactions(&input)? {
+                    truncated += transaction.truncated as u32;
+
+                    match store.ingest(&transaction)? {
+                        true => replayed += 1,
+                        false => skipped += 1,
+                    }
+                }
+            }
+
+            println!("replayed: {replayed}, already indexed: {skipped}");
+
+            if truncated > 0 {
+                eprintln!("{truncated} transactions had truncated logs, some of their events may be missing");
+            }
+        }
+        Command::House { house } => match store.house(&house)? {
+            Some(row) => print(
+                &["AUTHORITY", "PENDING_AUTHORITY", "FEE", "HOUSE_FEE_BPS", "POLICY"],
+                vec![vec![
+                    row.authority,
+                    row.pending_authority.unwrap_or_else(|| "-".to_string()),
+                    row.fee.to_string(),
+                    row.house_fee_bps.to_string(),
+                    row.policy,
+                ]],
+            ),
+            None => eprintln!("house {house} was never indexed"),
+        },
+        Command::Tickets { raffle } => print(
+            &["TICKET", "BUYER", "NUMBERS", "PRICE", "SLOT", "CLAIMED", "REFUNDED"],
+            store
+                .tickets(&raffle)?
+                .into_iter()
+                .map(|row| {
+                    vec![
+                        row.ticket,
+                        row.buyer,
+                        format!("{}-{}", row.first_ticket, row.last_ticket),
+                        row.price.to_string(),
+                        row.slot.to_string(),
+                        row.claimed_amount.map_or("-".to_string(), |amount| amount.to_string()),
+                        row.refunded_amount.map_or("-".to_string(), |amount| amount.to_string()),
+                    ]
+                })
+                .collect(),
+        ),
+        Command::Winnings { wallet } => print(
+            &["WALLET", "MINT", "PRIZES", "AMOUNT"],
+            store
+                .winnings(wallet.as_deref())?
+                .into_iter()
+                .map(|row| vec![row.wallet, mint(row.payment_mint), row.prizes.to_string(), row.amount.to_string()])
+                .collect(),
+        ),
+        Command::Revenue { house, period } => {
+            print(
+                &["PERIOD", "MINT", "CREATION_FEES", "HOUSE_FEES", "SWEPT", "WITHDRAWN"],
+                store
+                    .treasury_revenue(house.as_deref(), period)?
+                    .into_iter()
+                    .map(|row| {
+                        vec![
+                            row.period,
+                            mint(row.payment_mint),
+                            row.creation_fees.to_string(),
+                            row.house_fees.to_string(),
+                            row.swept.to_string(),
+                            row.withdrawn.to_string(),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    Ok(())
+}
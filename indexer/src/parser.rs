@@ -0,0 +1,166 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+
+use crate::events::BeeRafEvent;
+
+// Events of one confirmed transaction, the logged ones first and then the self-CPI ones
+pub struct TransactionEvents {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub events: Vec<BeeRafEvent>,
+    // The validator cut the logs, events emitted with emit! after the cut are lost
+    pub truncated: bool,
+}
+
+// Account keys of the message followed by the ones loaded from lookup tables,
+// as encoded by getTransaction with the json or jsonParsed encoding
+fn account_keys(transaction: &Value) -> Vec<String> {
+    let key = |key: &Value| match key {
+        Value::String(key) => Some(key.clone()),
+        key => key["pubkey"].as_str().map(str::to_string),
+    };
+
+    let mut keys: Vec<String> = transaction["transaction"]["message"]["accountKeys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(key)
+        .collect();
+
+    for loaded in ["writable", "readonly"] {
+        keys.extend(
+            transaction["meta"]["loadedAddresses"][loaded]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(key),
+        );
+    }
+
+    keys
+}
+
+// Events logged as `Program data:` while the beeraf program is the one running
+fn log_events(logs: &[Value], program_id: &str, events: &mut Vec<BeeRafEvent>) -> bool {
+    let mut invocations: Vec<&str> = vec![];
+
+    for log in logs.iter().filter_map(Value::as_str) {
+        if log == "Log truncated" {
+            return true;
+        }
+
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if invocations.last() == Some(&program_id) {
+                if let Some(event) = STANDARD.decode(data).ok().and_then(|data| BeeRafEvent::decode(&data)) {
+                    events.push(event);
+                }
+            }
+        } else if let Some((program, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                invocations.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                invocations.pop();
+            }
+        }
+    }
+
+    false
+}
+
+// Events emitted with emit_cpi!, a self-CPI whose data starts with the event tag
+fn cpi_events(transaction: &Value, keys: &[String], program_id: &str, events: &mut Vec<BeeRafEvent>) {
+    let instructions = transaction["meta"]["innerInstructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|inner| inner["instructions"].as_array().into_iter().flatten());
+
+    for instruction in instructions {
+        let program = match instruction["programIdIndex"].as_u64() {
+            Some(index) => keys.get(index as usize).map(String::as_str),
+            None => instruction["programId"].as_str(),
+        };
+
+        if program != Some(program_id) {
+            continue;
+        }
+
+        let Some(data) = instruction["data"].as_str().and_then(|data| bs58::decode(data).into_vec().ok()) else {
+            continue;
+        };
+
+        if let Some(event) = data.strip_prefix(&EVENT_IX_TAG_LE[..]).and_then(BeeRafEvent::decode) {
+            events.push(event);
+        }
+    }
+}
+
+// Parses a transaction as returned by getTransaction (the RPC response or its result).
+// Failed transactions have no effect on the program, so they give None
+pub fn parse_transaction(transaction: &Value) -> Result<Option<TransactionEvents>> {
+    let transaction = match transaction.get("result") {
+        Some(result) => result,
+        None => transaction,
+    };
+
+    if !transaction["meta"]["err"].is_null() {
+        return Ok(None);
+    }
+
+    let signature = transaction["transaction"]["signatures"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("Transaction without signature"))?
+        .to_string();
+    let slot = transaction["slot"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("Transaction {signature} without slot"))?;
+
+    let program_id = beeraf::ID.to_string();
+    let keys = account_keys(transaction);
+
+    let mut events = vec![];
+
+    let truncated = log_events(
+        transaction["meta"]["logMessages"].as_array().map(Vec::as_slice).unwrap_or_default(),
+        &program_id,
+        &mut events,
+    );
+
+    cpi_events(transaction, &keys, &program_id, &mut events);
+
+    Ok(Some(TransactionEvents {
+        signature,
+        slot,
+        block_time: transaction["blockTime"].as_i64(),
+        events,
+        truncated,
+    }))
+}
+
+// Reads transactions from JSON documents: a transaction, an array of them,
+// or several of them one after the other (JSON lines)
+pub fn parse_transactions(json: &str) -> Result<Vec<TransactionEvents>> {
+    let mut transactions = vec![];
+
+    for document in serde_json::Deserializer::from_str(json).into_iter::<Value>() {
+        let document = document?;
+
+        let items = match &document {
+            Value::Array(items) => items.iter().collect(),
+            document => vec![document],
+        };
+
+        for item in items {
+            transactions.extend(parse_transaction(item)?);
+        }
+    }
+
+    Ok(transactions)
+}
@@ -0,0 +1,615 @@
+use anyhow::Result;
+use beeraf::{RafflePolicy, RaffleStatus};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{events::BeeRafEvent, parser::TransactionEvents};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS transactions (
+        signature TEXT PRIMARY KEY,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        truncated INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS houses (
+        house TEXT PRIMARY KEY,
+        authority TEXT NOT NULL,
+        pending_authority TEXT,
+        fee INTEGER NOT NULL,
+        house_fee_bps INTEGER NOT NULL,
+        policy TEXT NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS raffles (
+        raffle TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        maker TEXT NOT NULL,
+        name TEXT NOT NULL,
+        ticket_price INTEGER NOT NULL,
+        capacity INTEGER NOT NULL,
+        payment_mint TEXT,
+        creation_fee INTEGER NOT NULL,
+        end_slot INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        commitment BLOB,
+        swept INTEGER,
+        swept_to_treasury INTEGER,
+        closed_block_time INTEGER,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tickets (
+        ticket TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        raffle TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        first_ticket INTEGER NOT NULL,
+        last_ticket INTEGER NOT NULL,
+        payment_mint TEXT,
+        price INTEGER NOT NULL,
+        maker_fee INTEGER NOT NULL,
+        vault_earning INTEGER NOT NULL,
+        house_fee INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tickets_raffle ON tickets (raffle);
+    CREATE TABLE IF NOT EXISTS resolutions (
+        raffle TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        seed BLOB NOT NULL,
+        roll BLOB NOT NULL,
+        winners TEXT NOT NULL,
+        tickets_sold INTEGER NOT NULL,
+        prize_pool INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS claims (
+        ticket TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        raffle TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        first_ticket INTEGER NOT NULL,
+        last_ticket INTEGER NOT NULL,
+        payment_mint TEXT,
+        claimed_amount INTEGER NOT NULL,
+        prize_released INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS claims_buyer ON claims (buyer);
+    CREATE TABLE IF NOT EXISTS refunds (
+        ticket TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        raffle TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        first_ticket INTEGER NOT NULL,
+        last_ticket INTEGER NOT NULL,
+        payment_mint TEXT,
+        refund INTEGER NOT NULL,
+        house_fee INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS cancellations (
+        raffle TEXT PRIMARY KEY,
+        house TEXT NOT NULL,
+        maker TEXT NOT NULL,
+        tickets_sold INTEGER NOT NULL,
+        creation_fee_refund INTEGER NOT NULL,
+        closed INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS withdrawals (
+        signature TEXT NOT NULL,
+        house TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        mint TEXT,
+        amount INTEGER NOT NULL,
+        slot INTEGER NOT NULL,
+        block_time INTEGER
+    );
+";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn format(&self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%Y-W%W",
+            Period::Month => "%Y-%m",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HouseRow {
+    pub authority: String,
+    // Proposed authority that has not accepted yet
+    pub pending_authority: Option<String>,
+    pub fee: u64,
+    pub house_fee_bps: u16,
+    // Raffle policy as JSON, bounds without a limit are null
+    pub policy: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TicketRow {
+    pub ticket: String,
+    pub buyer: String,
+    pub first_ticket: u32,
+    pub last_ticket: u32,
+    pub price: u64,
+    pub slot: u64,
+    // None until the ticket is scratched
+    pub claimed_amount: Option<u64>,
+    // None unless the ticket was refunded
+    pub refunded_amount: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WinningsRow {
+    pub wallet: String,
+    // None for SOL
+    pub payment_mint: Option<String>,
+    pub prizes: u64,
+    pub amount: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RevenueRow {
+    // Period of the block time, "unknown" for transactions without one
+    pub period: String,
+    pub payment_mint: Option<String>,
+    // Paid in SOL by the makers for each raffle
+    pub creation_fees: u64,
+    pub house_fees: u64,
+    // Unrefunded tickets of failed and cancelled raffles, swept to the treasury when they are closed
+    pub swept: u64,
+    pub withdrawn: u64,
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self { connection })
+    }
+
+    // Replays the events of a transaction. A transaction is only replayed once,
+    // so the same history can be ingested again; returns false when it already was
+    pub fn ingest(&mut self, transaction: &TransactionEvents) -> Result<bool> {
+        let db = self.connection.transaction()?;
+
+        let known = db
+            .query_row(
+                "SELECT 1 FROM transactions WHERE signature = ?1",
+                [&transaction.signature],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        if known {
+            return Ok(false);
+        }
+
+        db.execute(
+            "INSERT INTO transactions (signature, slot, block_time, truncated) VALUES (?1, ?2, ?3, ?4)",
+            params![transaction.signature, transaction.slot as i64, transaction.block_time, transaction.truncated],
+        )?;
+
+        let signature = &transaction.signature;
+        let block_time = transaction.block_time;
+
+        for event in &transaction.events {
+            match event {
+                BeeRafEvent::HouseInitialized(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO houses (house, authority, fee, house_fee_bps, policy, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            event.house.to_string(),
+                            event.authority.to_string(),
+                            event.fee as i64,
+                            event.house_fee_bps,
+                            policy_json(&event.policy),
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::RaffleCreated(event) => {
+                    // The status and sweep of a raffle seen before come from later events, keep them
+                    db.execute(
+                        "INSERT INTO raffles (raffle, house, maker, name, ticket_price, capacity, payment_mint, creation_fee, end_slot, status, commitment, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'Open', ?10, ?11, ?12, ?13)
+                         ON CONFLICT(raffle) DO UPDATE SET
+                             house = excluded.house,
+                             maker = excluded.maker,
+                             name = excluded.name,
+                             ticket_price = excluded.ticket_price,
+                             capacity = excluded.capacity,
+                             payment_mint = excluded.payment_mint,
+                             creation_fee = excluded.creation_fee,
+                             end_slot = excluded.end_slot,
+                             commitment = excluded.commitment,
+                             slot = excluded.slot,
+                             block_time = excluded.block_time,
+                             signature = excluded.signature",
+                        params![
+                            event.raffle.to_string(),
+                            event.house.to_string(),
+                            event.maker.to_string(),
+                            event.args.name,
+                            event.args.ticket_price as i64,
+                            event.args.capacity,
+                            event.args.payment_mint.map(|mint| mint.to_string()),
+                            event.creation_fee as i64,
+                            event.end_slot as i64,
+                            &event.args.commitment[..],
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::TicketPurchased(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO tickets (ticket, house, raffle, buyer, first_ticket, last_ticket, payment_mint, price, maker_fee, vault_earning, house_fee, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                        params![
+                            event.ticket.to_string(),
+                            event.house.to_string(),
+                            event.raffle.to_string(),
+                            event.buyer.to_string(),
+                            event.first_ticket,
+                            event.last_ticket,
+                            event.payment_mint.map(|mint| mint.to_string()),
+                            event.price as i64,
+                            event.maker_fee as i64,
+                            event.vault_earning as i64,
+                            event.house_fee as i64,
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::RaffleResolved(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO resolutions (raffle, house, seed, roll, winners, tickets_sold, prize_pool, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            event.raffle.to_string(),
+                            event.house.to_string(),
+                            &event.seed[..],
+                            &event.roll[..],
+                            serde_json::to_string(&event.winners)?,
+                            event.tickets_sold,
+                            event.prize_pool as i64,
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                    db.execute(
+                        "UPDATE raffles SET status = 'Resolved' WHERE raffle = ?1",
+                        [event.raffle.to_string()],
+                    )?;
+                }
+                BeeRafEvent::PrizeClaimed(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO claims (ticket, house, raffle, buyer, first_ticket, last_ticket, payment_mint, claimed_amount, prize_released, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        params![
+                            event.ticket.to_string(),
+                            event.house.to_string(),
+                            event.raffle.to_string(),
+                            event.buyer.to_string(),
+                            event.first_ticket,
+                            event.last_ticket,
+                            event.payment_mint.map(|mint| mint.to_string()),
+                            event.claimed_amount as i64,
+                            event.prize_released,
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::RaffleClosed(event) => {
+                    db.execute(
+                        "UPDATE raffles SET status = 'Closed', swept = ?2, swept_to_treasury = ?3, closed_block_time = ?4 WHERE raffle = ?1",
+                        params![
+                            event.raffle.to_string(),
+                            event.swept as i64,
+                            event.swept_to_treasury as i64,
+                            block_time,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::TreasuryWithdrawn(event) => {
+                    db.execute(
+                        "INSERT INTO withdrawals (signature, house, destination, mint, amount, slot, block_time)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            signature,
+                            event.house.to_string(),
+                            event.destination.to_string(),
+                            event.mint.map(|mint| mint.to_string()),
+                            event.amount as i64,
+                            event.slot as i64,
+                            block_time,
+                        ],
+                    )?;
+                }
+                BeeRafEvent::EntropyCaptured(event) => {
+                    // The first call only schedules the entropy slot, the status is kept
+                    if event.status == RaffleStatus::AwaitingRandomness {
+                        db.execute(
+                            "UPDATE raffles SET status = 'AwaitingRandomness' WHERE raffle = ?1",
+                            [event.raffle.to_string()],
+                        )?;
+                    }
+                }
+                BeeRafEvent::RandomnessRequested(event) => {
+                    db.execute(
+                        "UPDATE raffles SET status = 'AwaitingRandomness' WHERE raffle = ?1",
+                        [event.raffle.to_string()],
+                    )?;
+                }
+                BeeRafEvent::RaffleCancelled(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO cancellations (raffle, house, maker, tickets_sold, creation_fee_refund, closed, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            event.raffle.to_string(),
+                            event.house.to_string(),
+                            event.maker.to_string(),
+                            event.tickets_sold,
+                            event.creation_fee_refund as i64,
+                            event.closed,
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                    // Without tickets sold the raffle is closed right away
+                    db.execute(
+                        "UPDATE raffles SET status = ?2 WHERE raffle = ?1",
+                        params![event.raffle.to_string(), if event.closed { "Closed" } else { "Cancelled" }],
+                    )?;
+                }
+                BeeRafEvent::TicketRefunded(event) => {
+                    db.execute(
+                        "INSERT OR REPLACE INTO refunds (ticket, house, raffle, buyer, first_ticket, last_ticket, payment_mint, refund, house_fee, slot, block_time, signature)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        params![
+                            event.ticket.to_string(),
+                            event.house.to_string(),
+                            event.raffle.to_string(),
+                            event.buyer.to_string(),
+                            event.first_ticket,
+                            event.last_ticket,
+                            event.payment_mint.map(|mint| mint.to_string()),
+                            event.refund as i64,
+                            event.house_fee as i64,
+                            event.slot as i64,
+                            block_time,
+                            signature,
+                        ],
+                    )?;
+                    if event.status == RaffleStatus::Refunding {
+                        db.execute(
+                            "UPDATE raffles SET status = 'Refunding' WHERE raffle = ?1",
+                            [event.raffle.to_string()],
+                        )?;
+                    }
+                }
+                BeeRafEvent::PrizeReclaimed(event) => {
+                    if event.status == RaffleStatus::Refunding {
+                        db.execute(
+                            "UPDATE raffles SET status = 'Refunding' WHERE raffle = ?1",
+                            [event.raffle.to_string()],
+                        )?;
+                    }
+                }
+                BeeRafEvent::ConfigUpdated(event) => {
+                    db.execute(
+                        "UPDATE houses SET authority = ?2, fee = ?3, house_fee_bps = ?4, policy = ?5 WHERE house = ?1",
+                        params![
+                            event.house.to_string(),
+                            event.authority.to_string(),
+                            event.fee as i64,
+                            event.house_fee_bps,
+                            policy_json(&event.policy),
+                        ],
+                    )?;
+                }
+                BeeRafEvent::AuthorityProposed(event) => {
+                    db.execute(
+                        "UPDATE houses SET pending_authority = ?2 WHERE house = ?1",
+                        params![event.house.to_string(), event.pending_authority.to_string()],
+                    )?;
+                }
+                BeeRafEvent::AuthorityAccepted(event) => {
+                    db.execute(
+                        "UPDATE houses SET authority = ?2, pending_authority = NULL WHERE house = ?1",
+                        params![event.house.to_string(), event.authority.to_string()],
+                    )?;
+                }
+                BeeRafEvent::SeedCommitted(event) => {
+                    db.execute(
+                        "UPDATE raffles SET commitment = ?2 WHERE raffle = ?1",
+                        params![event.raffle.to_string(), &event.commitment[..]],
+                    )?;
+                }
+                BeeRafEvent::RaffleMigrated(event) => {
+                    // Legacy raffles were created before the events, so only the ones seen before are updated
+                    db.execute(
+                        "UPDATE raffles SET status = ?2 WHERE raffle = ?1",
+                        params![event.raffle.to_string(), format!("{:?}", event.status)],
+                    )?;
+                }
+            }
+        }
+
+        db.commit()?;
+
+        Ok(true)
+    }
+
+    // Current config of a house, None for a house that was never initialized
+    pub fn house(&self, house: &str) -> Result<Option<HouseRow>> {
+        let row = self
+            .connection
+            .query_row(
+                "SELECT authority, pending_authority, fee, house_fee_bps, policy FROM houses WHERE house = ?1",
+                [house],
+                |row| {
+                    Ok(HouseRow {
+                        authority: row.get(0)?,
+                        pending_authority: row.get(1)?,
+                        fee: row.get::<_, i64>(2)? as u64,
+                        house_fee_bps: row.get(3)?,
+                        policy: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(row)
+    }
+
+    // Tickets bought in a raffle, by ticket number
+    pub fn tickets(&self, raffle: &str) -> Result<Vec<TicketRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT tickets.ticket, tickets.buyer, tickets.first_ticket, tickets.last_ticket, tickets.price, tickets.slot, claims.claimed_amount, refunds.refund
+             FROM tickets
+             LEFT JOIN claims ON claims.ticket = tickets.ticket
+             LEFT JOIN refunds ON refunds.ticket = tickets.ticket
+             WHERE tickets.raffle = ?1
+             ORDER BY tickets.first_ticket",
+        )?;
+
+        let rows = statement.query_map([raffle], |row| {
+            Ok(TicketRow {
+                ticket: row.get(0)?,
+                buyer: row.get(1)?,
+                first_ticket: row.get(2)?,
+                last_ticket: row.get(3)?,
+                price: row.get::<_, i64>(4)? as u64,
+                slot: row.get::<_, i64>(5)? as u64,
+                claimed_amount: row.get::<_, Option<i64>>(6)?.map(|amount| amount as u64),
+                refunded_amount: row.get::<_, Option<i64>>(7)?.map(|amount| amount as u64),
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // Prizes paid per wallet and payment mint, from the scratched tickets that won
+    pub fn winnings(&self, wallet: Option<&str>) -> Result<Vec<WinningsRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT buyer, payment_mint, COUNT(*), SUM(claimed_amount)
+             FROM claims
+             WHERE claimed_amount > 0 AND (?1 IS NULL OR buyer = ?1)
+             GROUP BY buyer, payment_mint
+             ORDER BY SUM(claimed_amount) DESC, buyer",
+        )?;
+
+        let rows = statement.query_map([wallet], |row| {
+            Ok(WinningsRow {
+                wallet: row.get(0)?,
+                payment_mint: row.get(1)?,
+                prizes: row.get::<_, i64>(2)? as u64,
+                amount: row.get::<_, i64>(3)? as u64,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // Creation and house fees collected by the treasury and withdrawn from it, per period and payment mint.
+    // The creation fee given back on a cancel is left out, and the house fees only reach the
+    // treasury when the raffle is resolved, so refunded tickets never count. What is left of a failed
+    // or cancelled raffle counts when it is closed
+    pub fn treasury_revenue(&self, house: Option<&str>, period: Period) -> Result<Vec<RevenueRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT period, payment_mint, SUM(creation_fee), SUM(house_fee), SUM(swept), SUM(withdrawn) FROM (
+                 SELECT COALESCE(strftime(?1, raffles.block_time, 'unixepoch'), 'unknown') AS period, NULL AS payment_mint,
+                     raffles.creation_fee - COALESCE(cancellations.creation_fee_refund, 0) AS creation_fee, 0 AS house_fee,
+                     0 AS swept, 0 AS withdrawn
+                 FROM raffles LEFT JOIN cancellations ON cancellations.raffle = raffles.raffle
+                 WHERE ?2 IS NULL OR raffles.house = ?2
+                 UNION ALL
+                 SELECT COALESCE(strftime(?1, resolutions.block_time, 'unixepoch'), 'unknown'), tickets.payment_mint, 0, tickets.house_fee, 0, 0
+                 FROM tickets JOIN resolutions ON resolutions.raffle = tickets.raffle
+                 WHERE ?2 IS NULL OR tickets.house = ?2
+                 UNION ALL
+                 SELECT COALESCE(strftime(?1, closed_block_time, 'unixepoch'), 'unknown'), payment_mint, 0, 0, swept_to_treasury, 0
+                 FROM raffles WHERE swept_to_treasury > 0 AND (?2 IS NULL OR house = ?2)
+                 UNION ALL
+                 SELECT COALESCE(strftime(?1, block_time, 'unixepoch'), 'unknown'), mint, 0, 0, 0, amount
+                 FROM withdrawals WHERE ?2 IS NULL OR house = ?2
+             )
+             GROUP BY period, payment_mint
+             ORDER BY period, payment_mint",
+        )?;
+
+        let rows = statement.query_map(params![period.format(), house], |row| {
+            Ok(RevenueRow {
+                period: row.get(0)?,
+                payment_mint: row.get(1)?,
+                creation_fees: row.get::<_, i64>(2)? as u64,
+                house_fees: row.get::<_, i64>(3)? as u64,
+                swept: row.get::<_, i64>(4)? as u64,
+                withdrawn: row.get::<_, i64>(5)? as u64,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn policy_json(policy: &RafflePolicy) -> String {
+    serde_json::json!({
+        "max_raffle_fee_bps": policy.max_raffle_fee_bps,
+        "min_ticket_price": policy.min_ticket_price,
+        "max_ticket_price": policy.max_ticket_price,
+        "min_slot_interval": policy.min_slot_interval,
+        "max_slot_interval": policy.max_slot_interval,
+        "cancel_refund_bps": policy.cancel_refund_bps,
+    })
+    .to_string()
+}
@@ -0,0 +1,395 @@
+// Replays getTransaction JSON built from the program events, sent through the
+// emit_cpi! self-CPI (or logged with emit!), and checks the history queries.
+
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::Pubkey, Event};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use beeraf::{
+    AuthorityAccepted, AuthorityProposed, ConfigUpdated, CreateRaffleArgs, HouseInitialized, Prize, PrizeClaimed,
+    RaffleCancelled, RaffleClosed, RaffleCreated, RaffleEnd, RafflePolicy, RaffleResolved, RaffleStatus, RandomnessSource,
+    TicketPurchased, TicketRefunded, TreasuryWithdrawn,
+};
+use beeraf_indexer::{parse_transactions, Period, Store};
+use serde_json::{json, Value};
+
+const NOVEMBER: i64 = 1_700_000_000;
+const DECEMBER: i64 = 1_702_000_000;
+
+// A transaction logging the events with emit!
+fn logged(signature: &str, block_time: i64, events: &[Vec<u8>]) -> Value {
+    let program = beeraf::ID.to_string();
+
+    let mut logs = vec![format!("Program {program} invoke [1]")];
+    logs.extend(events.iter().map(|data| format!("Program data: {}", STANDARD.encode(data))));
+    logs.push(format!("Program {program} success"));
+
+    json!({
+        "slot": 100,
+        "blockTime": block_time,
+        "transaction": {
+            "signatures": [signature],
+            "message": { "accountKeys": [Pubkey::new_unique().to_string(), program] },
+        },
+        "meta": { "err": null, "logMessages": logs, "innerInstructions": [] },
+    })
+}
+
+// A transaction sending the events through the self-CPI of emit_cpi!
+fn self_cpi(signature: &str, block_time: i64, events: &[Vec<u8>]) -> Value {
+    let instructions: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            let mut data = EVENT_IX_TAG_LE.to_vec();
+            data.extend(event);
+
+            json!({ "programIdIndex": 1, "accounts": [], "data": bs58::encode(data).into_string() })
+        })
+        .collect();
+
+    json!({
+        "slot": 200,
+        "blockTime": block_time,
+        "transaction": {
+            "signatures": [signature],
+            "message": { "accountKeys": [Pubkey::new_unique().to_string(), beeraf::ID.to_string()] },
+        },
+        "meta": {
+            "err": null,
+            "logMessages": [],
+            "innerInstructions": [{ "index": 0, "instructions": instructions }],
+        },
+    })
+}
+
+fn buy(house: Pubkey, raffle: Pubkey, buyer: Pubkey, ticket: Pubkey, first: u32, last: u32) -> Vec<u8> {
+    let price = (last - first + 1) as u64 * 1_000;
+
+    TicketPurchased {
+        house,
+        raffle,
+        buyer,
+        ticket,
+        first_ticket: first,
+        last_ticket: last,
+        payment_mint: None,
+        price,
+        maker_fee: price / 20,
+        vault_earning: price - price / 20 - price / 50,
+        house_fee: price / 50,
+        slot: 100,
+    }
+    .data()
+}
+
+fn create(house: Pubkey, raffle: Pubkey) -> Vec<u8> {
+    let args = CreateRaffleArgs {
+        name: "Raffle".to_string(),
+        uri: "https://example.com/raffle.json".to_string(),
+        ticket_price: 1_000,
+        raffle_fee: 500,
+        end: RaffleEnd::Slot { slot_interval: 1_000 },
+        start_timestamp: None,
+        end_on_sell_out: false,
+        capacity: 10,
+        min_tickets: None,
+        commitment: [1; 32],
+        randomness_source: RandomnessSource::CommitReveal,
+        prize_tiers: vec![],
+        payment_mint: None,
+        prize: Prize::None,
+    };
+
+    RaffleCreated {
+        house,
+        maker: Pubkey::new_unique(),
+        raffle,
+        raffle_config: Pubkey::new_unique(),
+        args,
+        end_slot: 1_050,
+        creation_fee: 100,
+        slot: 50,
+    }
+    .data()
+}
+
+#[test]
+fn replays_raffle_history() {
+    let house = Pubkey::new_unique();
+    let raffle = Pubkey::new_unique();
+    let buyers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let tickets = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let resolved = RaffleResolved {
+        house,
+        raffle,
+        randomness_source: RandomnessSource::CommitReveal,
+        seed: [2; 32],
+        entropy: [3; 32],
+        roll: [4; 32],
+        winners: vec![3],
+        tickets_sold: 5,
+        prize_pool: 4_650,
+        slot: 150,
+    };
+
+    let claimed = PrizeClaimed {
+        house,
+        raffle,
+        buyer: buyers[1],
+        ticket: tickets[1],
+        winner: 3,
+        first_ticket: 3,
+        last_ticket: 5,
+        payment_mint: None,
+        claimed_amount: 4_650,
+        prize_released: false,
+        slot: 200,
+    };
+
+    let withdraw = TreasuryWithdrawn {
+        house,
+        amount: 50,
+        destination: house,
+        mint: None,
+        slot: 300,
+    };
+
+    // A failed transaction and an event logged while another program runs are left out
+    let mut failed = logged("failed", NOVEMBER, &[buy(house, raffle, buyers[0], Pubkey::new_unique(), 9, 9)]);
+    failed["meta"]["err"] = json!({ "InstructionError": [0, "Custom"] });
+
+    let mut spoofed = logged("spoofed", NOVEMBER, &[]);
+    spoofed["meta"]["logMessages"] = json!([
+        "Program 11111111111111111111111111111111 invoke [1]",
+        format!("Program data: {}", STANDARD.encode(buy(house, raffle, buyers[0], Pubkey::new_unique(), 9, 9))),
+        "Program 11111111111111111111111111111111 success",
+    ]);
+
+    let history = json!([
+        self_cpi("create", NOVEMBER, &[create(house, raffle)]),
+        self_cpi("buy-1", NOVEMBER, &[buy(house, raffle, buyers[0], tickets[0], 1, 2)]),
+        logged("buy-2", DECEMBER, &[buy(house, raffle, buyers[1], tickets[1], 3, 5)]),
+        failed,
+        spoofed,
+        self_cpi("solve", DECEMBER, &[resolved.data()]),
+        self_cpi("scratch", DECEMBER, &[claimed.data()]),
+    ]);
+
+    // Withdrawals come as JSON lines, wrapped in the RPC response
+    let withdrawal = json!({ "jsonrpc": "2.0", "id": 1, "result": self_cpi("withdraw", DECEMBER, &[withdraw.data()]) });
+    let input = format!("{history}\n{withdrawal}");
+
+    let transactions = parse_transactions(&input).unwrap();
+    assert_eq!(transactions.len(), 7);
+
+    let mut store = Store::open_in_memory().unwrap();
+
+    for transaction in &transactions {
+        assert!(store.ingest(transaction).unwrap());
+    }
+
+    // Ingesting the same history again changes nothing
+    assert!(!store.ingest(&transactions[0]).unwrap());
+
+    let sold = store.tickets(&raffle.to_string()).unwrap();
+    assert_eq!(sold.len(), 2);
+    assert_eq!((sold[0].first_ticket, sold[0].last_ticket), (1, 2));
+    assert_eq!(sold[0].claimed_amount, None);
+    assert_eq!(sold[1].ticket, tickets[1].to_string());
+    assert_eq!(sold[1].claimed_amount, Some(4_650));
+    assert_eq!(sold[1].refunded_amount, None);
+
+    let winnings = store.winnings(None).unwrap();
+    assert_eq!(winnings.len(), 1);
+    assert_eq!(winnings[0].wallet, buyers[1].to_string());
+    assert_eq!((winnings[0].prizes, winnings[0].amount), (1, 4_650));
+
+    assert!(store.winnings(Some(&buyers[0].to_string())).unwrap().is_empty());
+
+    // The house fees reach the treasury when the raffle is resolved, in December
+    let revenue = store.treasury_revenue(Some(&house.to_string()), Period::Month).unwrap();
+    assert_eq!(revenue.len(), 2);
+    assert_eq!(
+        (revenue[0].period.as_str(), revenue[0].creation_fees, revenue[0].house_fees, revenue[0].withdrawn),
+        ("2023-11", 100, 0, 0)
+    );
+    assert_eq!(
+        (revenue[1].period.as_str(), revenue[1].creation_fees, revenue[1].house_fees, revenue[1].withdrawn),
+        ("2023-12", 0, 100, 50)
+    );
+
+    assert!(store.treasury_revenue(Some(&Pubkey::new_unique().to_string()), Period::Day).unwrap().is_empty());
+}
+
+#[test]
+fn leaves_refunds_and_cancels_out_of_revenue() {
+    let house = Pubkey::new_unique();
+    let raffles = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let buyer = Pubkey::new_unique();
+    let ticket = Pubkey::new_unique();
+
+    // The first raffle is cancelled with a ticket sold, which gets refunded
+    let cancelled = RaffleCancelled {
+        house,
+        raffle: raffles[0],
+        maker: Pubkey::new_unique(),
+        tickets_sold: 2,
+        creation_fee_refund: 0,
+        closed: false,
+        slot: 150,
+    };
+
+    let refunded = TicketRefunded {
+        house,
+        raffle: raffles[0],
+        buyer,
+        ticket,
+        first_ticket: 1,
+        last_ticket: 2,
+        payment_mint: None,
+        refund: 2_000,
+        house_fee: 40,
+        status: RaffleStatus::Cancelled,
+        slot: 200,
+    };
+
+    // The second one is cancelled without tickets, and half of its creation fee given back
+    let closed = RaffleCancelled {
+        house,
+        raffle: raffles[1],
+        maker: Pubkey::new_unique(),
+        tickets_sold: 0,
+        creation_fee_refund: 50,
+        closed: true,
+        slot: 150,
+    };
+
+    // A third raffle fails and gets closed without its ticket refunded, the treasury takes it
+    let swept = RaffleClosed {
+        house,
+        raffle: raffles[2],
+        maker: Pubkey::new_unique(),
+        status: RaffleStatus::Refunding,
+        swept: 50,
+        swept_to_treasury: 950,
+        collection_burned: false,
+        slot: 300,
+    };
+
+    let history = json!([
+        self_cpi("create-1", NOVEMBER, &[create(house, raffles[0])]),
+        self_cpi("create-2", NOVEMBER, &[create(house, raffles[1])]),
+        self_cpi("create-3", NOVEMBER, &[create(house, raffles[2])]),
+        self_cpi("buy", NOVEMBER, &[buy(house, raffles[0], buyer, ticket, 1, 2)]),
+        self_cpi("buy-3", NOVEMBER, &[buy(house, raffles[2], buyer, Pubkey::new_unique(), 1, 1)]),
+        self_cpi("cancel-1", DECEMBER, &[cancelled.data()]),
+        self_cpi("cancel-2", DECEMBER, &[closed.data()]),
+        self_cpi("refund", DECEMBER, &[refunded.data()]),
+        self_cpi("close-3", DECEMBER, &[swept.data()]),
+    ]);
+
+    let transactions = parse_transactions(&history.to_string()).unwrap();
+    let mut store = Store::open_in_memory().unwrap();
+
+    for transaction in &transactions {
+        assert!(store.ingest(transaction).unwrap());
+    }
+
+    let sold = store.tickets(&raffles[0].to_string()).unwrap();
+    assert_eq!(sold.len(), 1);
+    assert_eq!(sold[0].refunded_amount, Some(2_000));
+
+    let revenue = store.treasury_revenue(None, Period::Month).unwrap();
+    assert_eq!(revenue.len(), 2);
+    assert_eq!(
+        (revenue[0].period.as_str(), revenue[0].creation_fees, revenue[0].house_fees, revenue[0].swept),
+        ("2023-11", 250, 0, 0)
+    );
+    assert_eq!(
+        (revenue[1].period.as_str(), revenue[1].creation_fees, revenue[1].house_fees, revenue[1].swept),
+        ("2023-12", 0, 0, 950)
+    );
+}
+
+#[test]
+fn tracks_house_config_changes() {
+    let house = Pubkey::new_unique();
+    let authorities = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let initialized = HouseInitialized {
+        house,
+        authority: authorities[0],
+        fee: 100,
+        house_fee_bps: 200,
+        policy: RafflePolicy::default(),
+        slot: 10,
+    };
+
+    let updated = ConfigUpdated {
+        house,
+        authority: authorities[0],
+        fee: 300,
+        house_fee_bps: 0,
+        policy: RafflePolicy { max_ticket_price: Some(5_000), ..Default::default() },
+        slot: 20,
+    };
+
+    let proposed = AuthorityProposed {
+        house,
+        authority: authorities[0],
+        pending_authority: authorities[1],
+        slot: 30,
+    };
+
+    let accepted = AuthorityAccepted {
+        house,
+        previous_authority: authorities[0],
+        authority: authorities[1],
+        slot: 40,
+    };
+
+    let history = json!([
+        self_cpi("initialize", NOVEMBER, &[initialized.data()]),
+        self_cpi("update", NOVEMBER, &[updated.data()]),
+        self_cpi("propose", DECEMBER, &[proposed.data()]),
+    ]);
+
+    let transactions = parse_transactions(&history.to_string()).unwrap();
+    let mut store = Store::open_in_memory().unwrap();
+
+    for transaction in &transactions {
+        assert!(store.ingest(transaction).unwrap());
+    }
+
+    let row = store.house(&house.to_string()).unwrap().unwrap();
+    assert_eq!((row.fee, row.house_fee_bps), (300, 0));
+    assert_eq!(row.authority, authorities[0].to_string());
+    assert_eq!(row.pending_authority, Some(authorities[1].to_string()));
+
+    let policy: Value = serde_json::from_str(&row.policy).unwrap();
+    assert_eq!(policy["max_ticket_price"], json!(5_000));
+    assert_eq!(policy["min_ticket_price"], Value::Null);
+
+    // The proposed authority takes over once it accepts
+    let accept = parse_transactions(&self_cpi("accept", DECEMBER, &[accepted.data()]).to_string()).unwrap();
+    assert!(store.ingest(&accept[0]).unwrap());
+
+    let row = store.house(&house.to_string()).unwrap().unwrap();
+    assert_eq!(row.authority, authorities[1].to_string());
+    assert_eq!(row.pending_authority, None);
+
+    assert!(store.house(&Pubkey::new_unique().to_string()).unwrap().is_none());
+}
+
+#[test]
+fn flags_truncated_logs() {
+    let mut transaction = logged("truncated", NOVEMBER, &[]);
+    transaction["meta"]["logMessages"] = json!([
+        format!("Program {} invoke [1]", beeraf::ID),
+        "Log truncated",
+    ]);
+
+    let transactions = parse_transactions(&transaction.to_string()).unwrap();
+
+    assert!(transactions[0].truncated);
+    assert!(transactions[0].events.is_empty());
+}
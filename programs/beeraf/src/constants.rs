@@ -3,3 +3,49 @@ use anchor_lang::prelude::*;
 #[constant]
 pub const SEED: &str = "anchor";
 
+
+// Slots the winner has to claim the prize before the maker can sweep it (~7 days)
+#[constant]
+pub const CLAIM_PERIOD: u64 = 1_512_000;
+
+// Seconds the winner has to claim the prize of a raffle ending at a unix timestamp (7 days)
+#[constant]
+pub const CLAIM_PERIOD_SECONDS: i64 = 604_800;
+
+// Raffles created before the vault was seeded by the raffle have version 0
+#[constant]
+pub const VAULT_VERSION: u8 = 1;
+
+#[constant]
+pub const MAX_CAPACITY: u32 = 100_000;
+
+// Version of the TicketData layout written in the ticket AppData plugin
+#[constant]
+pub const TICKET_DATA_VERSION: u8 = 1;
+
+// Version of the RaffleConfig layout, raffles created before it was stored have version 0
+#[constant]
+pub const RAFFLE_CONFIG_VERSION: u8 = 1;
+
+// Slots between the first capture_entropy call and the slot whose hash is captured,
+// so the hash can not be known when the slot is chosen
+#[constant]
+pub const ENTROPY_DELAY: u64 = 10;
+
+// Slot hashes kept by the SlotHashes sysvar, the entropy must be captured before its slot leaves them
+#[constant]
+pub const SLOT_HASHES_WINDOW: u64 = 512;
+
+// Slots (about a day) the maker or the oracle have to reveal the randomness
+// once the entropy is captured or the VRF request bound, after them the raffle gets refunded
+#[constant]
+pub const RANDOMNESS_TIMEOUT: u64 = 216_000;
+
+// Seconds (a day) the maker of a VRF raffle ending at a unix timestamp has to bind the request
+// once ticket sales are over, RANDOMNESS_TIMEOUT slots for the other raffles
+#[constant]
+pub const RANDOMNESS_TIMEOUT_SECONDS: i64 = 86_400;
+
+// Maximum number of prize tiers (winners) per raffle
+#[constant]
+pub const MAX_WINNERS: usize = 10;
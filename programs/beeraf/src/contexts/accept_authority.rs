@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::BeeRafError, AuthorityAccepted, Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = config.pending_authority == Some(new_authority.key()) @ BeeRafError::InvalidAuthority,
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> AcceptAuthority<'info> {
+    pub fn accept_authority(&mut self) -> Result<AuthorityAccepted> {
+        let previous_authority = self.config.authority;
+
+        self.config.authority = self.new_authority.key();
+        self.config.pending_authority = None;
+
+        Ok(AuthorityAccepted {
+            house: self.house.key(),
+            previous_authority,
+            authority: self.config.authority,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
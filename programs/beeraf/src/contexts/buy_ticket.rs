@@ -1,11 +1,13 @@
 use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use mpl_core::{
-    accounts::BaseCollectionV1, fetch_plugin, instructions::CreateV2CpiBuilder, types::{AppDataInitInfo, Attribute, Attributes, ExternalPluginAdapterInitInfo, ExternalPluginAdapterSchema, PermanentBurnDelegate, PermanentFreezeDelegate, PermanentTransferDelegate, Plugin, PluginAuthority, PluginAuthorityPair, PluginType}, ID as MPL_CORE_ID
+    accounts::BaseCollectionV1, instructions::CreateV2CpiBuilder, types::{AppDataInitInfo, Attribute, Attributes, ExternalPluginAdapterInitInfo, ExternalPluginAdapterSchema, PermanentBurnDelegate, PermanentFreezeDelegate, PermanentTransferDelegate, Plugin, PluginAuthority, PluginAuthorityPair}, ID as MPL_CORE_ID
 };
 
-use crate::{error::BeeRafError, BuyEvent, Config, RaffleConfig};
+use crate::{error::BeeRafError, payment::token_accounts, Config, RaffleConfig, RaffleStatus, TicketData, TicketPurchased};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct BuyTicket<'info> {
     #[account(mut)]
@@ -14,9 +16,6 @@ pub struct BuyTicket<'info> {
     /// CHECK: We don't make anything on this account
     pub house: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub maker: SystemAccount<'info>,
-
     #[account(
         seeds = [b"treasury", house.key().as_ref()],
         bump = config.treasury_bump
@@ -36,6 +35,7 @@ pub struct BuyTicket<'info> {
     pub raffle: Account<'info, BaseCollectionV1>,
 
     #[account(
+        mut,
         seeds = [
             b"raffle",
             house.key().as_ref(), 
@@ -50,62 +50,73 @@ pub struct BuyTicket<'info> {
 
     #[account(
         mut,
-        seeds = [b"vault", maker.key().as_ref()],
+        seeds = [b"vault", raffle.key().as_ref()],
         bump = raffle_config.vault_bump
     )]
     vault: SystemAccount<'info>,
 
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub buyer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub vault_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
     #[account(address = MPL_CORE_ID)]
     /// CHECK: This is checked by the address constraint
     pub mpl_core_program: UncheckedAccount<'info>,
 
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> BuyTicket<'info> {
-    pub fn buy_ticket(&mut self, args: BuyTicketArgs) -> Result<()> {
+    pub fn buy_ticket(&mut self, args: BuyTicketArgs) -> Result<TicketPurchased> {
         let house = self.house.key();
         let raffle = self.raffle.key();
 
-        let current_slot = Clock::get()?.slot;
+        let clock = Clock::get()?;
+        let current_slot = clock.slot;
 
-        require!(current_slot <= self.raffle_config.slot, BeeRafError::TimeExpired);
-
-         // Check that the maximum number of tickets has not been reached yet
-         let (_, collection_attribute_list, _) = fetch_plugin::<BaseCollectionV1, Attributes>(
-            &self.raffle.to_account_info(),
-            PluginType::Attributes,
-        )?;
+        require!(self.raffle_config.status == RaffleStatus::Open, BeeRafError::RaffleNotOpen);
+        require!(self.raffle_config.has_started(&clock), BeeRafError::NotStarted);
+        require!(!self.raffle_config.is_over(&clock), BeeRafError::TimeExpired);
 
-         // Search for the Capacity attribute
-        let capacity_attribute = collection_attribute_list
-        .attribute_list
-        .iter()
-        .find(|attr| attr.key == "Capacity")
-        .ok_or(BeeRafError::MissingAttribute)?;
+        require!(args.quantity > 0, BeeRafError::InvalidQuantity);
 
-        // Unwrap the Capacity attribute value
-        let capacity = capacity_attribute
-        .value
-        .parse::<u32>()
-        .map_err(|_| BeeRafError::NumericalOverflow)?;
+        // The ticket holds the numbers from first to last
+        let sold = self.raffle_config.sold(self.raffle.num_minted);
+        let first = sold
+            .checked_add(1)
+            .ok_or(BeeRafError::NumericalOverflow)?;
+        let last = sold
+            .checked_add(args.quantity)
+            .ok_or(BeeRafError::NumericalOverflow)?;
 
+        // Check that the maximum number of tickets has not been reached yet
         require!(
-            self.raffle.num_minted < capacity,
+            last <= self.raffle_config.capacity,
             BeeRafError::MaximumTicketsReached
         );
 
+        let price = self.raffle_config.ticket_price
+            .checked_mul(args.quantity as u64)
+            .ok_or(BeeRafError::NumericalOverflow)?;
+
         // Add an Attribute Plugin that will hold the ticket details
         let mut ticket_plugin: Vec<PluginAuthorityPair> = vec![];
 
         let attribute_list: Vec<Attribute> = vec![
             Attribute {
                 key: "Ticket Number".to_string(),
-                value: self.raffle
-                    .num_minted
-                    .checked_add(1)
-                    .ok_or(BeeRafError::NumericalOverflow)?
-                    .to_string(),
+                value: first.to_string(),
+            },
+            Attribute {
+                key: "Last Ticket Number".to_string(),
+                value: last.to_string(),
             },
         ];
         ticket_plugin.push(PluginAuthorityPair {
@@ -155,39 +166,98 @@ impl<'info> BuyTicket<'info> {
             .external_plugin_adapters(ticket_external_plugin)
             .invoke_signed(&[signer_seeds])?;
 
-        let maker_fee = (self.raffle_config.ticket_price * self.raffle_config.raffle_fee) / 10_000;
-                let vault_earning = self.raffle_config.ticket_price - maker_fee;
+        // Record the ticket state in its AppData plugin
+        TicketData::new(current_slot, price).write(
+            &self.mpl_core_program.to_account_info(),
+            &self.ticket.to_account_info(),
+            &self.raffle.to_account_info(),
+            &self.raffle_config.to_account_info(),
+            &self.buyer.to_account_info(),
+            &self.system_program.to_account_info(),
+            &[signer_seeds],
+        )?;
 
-        emit!(BuyEvent {
+        // The house cut is the one the raffle was created with, the house can not raise it on open raffles
+        let maker_fee = RaffleConfig::bps_share(price, self.raffle_config.raffle_fee)?;
+        let house_fee = RaffleConfig::bps_share(price, self.raffle_config.house_fee_bps as u64)?;
+        let vault_earning = price
+            .checked_sub(maker_fee)
+            .and_then(|earning| earning.checked_sub(house_fee))
+            .ok_or(BeeRafError::NumericalOverflow)?;
+
+        let event = TicketPurchased {
+            house,
+            raffle,
+            buyer: self.buyer.key(),
+            ticket: self.ticket.key(),
+            first_ticket: first,
+            last_ticket: last,
+            payment_mint: self.raffle_config.payment_mint,
+            price,
             maker_fee,
             vault_earning,
-        });
+            house_fee,
+            slot: current_slot,
+        };
         msg!("maker_fee: {}", maker_fee);
+        msg!("house_fee: {}", house_fee);
         msg!("vault_earning: {}", vault_earning);
 
-        let cpi_program = self.system_program.to_account_info();
+        let token = token_accounts(
+            &self.raffle_config,
+            &self.vault.key(),
+            &self.payment_mint,
+            &self.vault_token_account,
+            &self.token_program,
+        )?;
 
-        let cpi_accounts = Transfer {
-            from: self.buyer.to_account_info(),
-            to: self.maker.to_account_info(),
+        // Amounts that actually arrive, token transfer fees are taken from them.
+        // The maker fee and the house cut stay in the vault until the raffle gets resolved,
+        // so buyers get the whole price back in a refund
+        let (fee_received, vault_received, house_fee_received) = match token {
+            Some(token) => {
+                let buyer = self.buyer.to_account_info();
+                let buyer_token_account = token.owned_by(&self.buyer_token_account, &self.buyer.key())?;
+
+                (
+                    token.transfer(buyer_token_account, token.vault_token_account, &buyer, maker_fee, &[])?,
+                    token.transfer(buyer_token_account, token.vault_token_account, &buyer, vault_earning, &[])?,
+                    token.transfer(buyer_token_account, token.vault_token_account, &buyer, house_fee, &[])?,
+                )
+            }
+            None => {
+                let cpi_accounts = Transfer {
+                    from: self.buyer.to_account_info(),
+                    to: self.vault.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+                transfer(cpi_ctx, price)?;
+
+                (maker_fee, vault_earning, house_fee)
+            }
         };
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        self.raffle_config.escrowed_fees = self.raffle_config.escrowed_fees
+            .checked_add(fee_received)
+            .ok_or(BeeRafError::NumericalOverflow)?;
 
-        transfer(cpi_ctx, maker_fee)?;
-
-        let cpi_accounts = Transfer {
-            from: self.buyer.to_account_info(),
-            to: self.vault.to_account_info(),
-        };
+        self.raffle_config.pot = self.raffle_config.pot
+            .checked_add(vault_received)
+            .ok_or(BeeRafError::NumericalOverflow)?;
 
-        let cpi_program = self.system_program.to_account_info();
+        self.raffle_config.escrowed_house_fees = self.raffle_config.escrowed_house_fees
+            .checked_add(house_fee_received)
+            .ok_or(BeeRafError::NumericalOverflow)?;
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        self.raffle_config.tickets_sold = last;
 
-        transfer(cpi_ctx, vault_earning)?;
+        if last == self.raffle_config.capacity {
+            self.raffle_config.status = RaffleStatus::SoldOut;
+        }
 
-        Ok(())
+        Ok(event)
     }
 }
 
@@ -195,4 +265,6 @@ impl<'info> BuyTicket<'info> {
 pub struct BuyTicketArgs {
     pub name: String,
     pub uri: String,
+    // Number of tickets held by the minted asset
+    pub quantity: u32,
 }
@@ -0,0 +1,201 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenInterface}};
+use mpl_core::{accounts::BaseCollectionV1, instructions::BurnCollectionV1CpiBuilder, ID as MPL_CORE_ID};
+
+use crate::{error::BeeRafError, prize::PrizeAccounts, Config, Prize, RaffleCancelled, RaffleConfig, RaffleStatus};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        constraint = raffle_config.authority == maker.key(),
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump = raffle_config.vault_bump
+    )]
+    vault: SystemAccount<'info>,
+
+    // Only for raffles with an escrowed prize
+    /// CHECK: The mpl-core asset given as prize, it is checked against the raffle config
+    #[account(mut)]
+    pub prize_asset: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Collection of the prize asset, it is checked by mpl-core
+    #[account(mut)]
+    pub prize_collection: Option<UncheckedAccount<'info>>,
+
+    pub prize_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// CHECK: Token account of the vault holding the prize, it is checked by the token program
+    #[account(mut)]
+    pub vault_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of the maker for the prize mint, it is created by the associated token program
+    #[account(mut)]
+    pub maker_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    #[account(address = MPL_CORE_ID)]
+    /// CHECK: This is checked by the address constraint
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub prize_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelRaffle<'info> {
+    pub fn cancel_raffle(&mut self) -> Result<RaffleCancelled> {
+        let house = self.house.key();
+        let raffle = self.raffle.key();
+
+        let clock = Clock::get()?;
+
+        require!(self.raffle_config.status != RaffleStatus::Cancelled, BeeRafError::RaffleCancelled);
+        require!(
+            matches!(self.raffle_config.status, RaffleStatus::Open | RaffleStatus::SoldOut),
+            BeeRafError::CancelNotAvailable
+        );
+
+        // Tickets were sold, so the raffle moves to the refund state.
+        // It is only possible while tickets are on sale, before the roll can be known.
+        // The maker fees and house cuts are still in the vault, so holders get the full price back.
+        if self.raffle.num_minted > 0 {
+            require!(
+                !self.raffle_config.sales_ended(&clock, self.raffle.num_minted),
+                BeeRafError::CancelNotAvailable
+            );
+
+            self.raffle_config.status = RaffleStatus::Cancelled;
+            self.raffle_config.set_resolved(&clock);
+
+            return Ok(RaffleCancelled {
+                house,
+                raffle,
+                maker: self.maker.key(),
+                tickets_sold: self.raffle_config.sold(self.raffle.num_minted),
+                creation_fee_refund: 0,
+                closed: false,
+                slot: clock.slot,
+            });
+        }
+
+        // Nothing was sold, so the escrowed prize goes back and the raffle is closed
+        if self.raffle_config.prize != Prize::None && !self.raffle_config.prize_released {
+            let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
+            let signer_seeds = &[&seeds[..]][..];
+
+            PrizeAccounts {
+                mpl_core_program: &self.mpl_core_program.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+                prize_asset: &self.prize_asset,
+                prize_collection: &self.prize_collection,
+                prize_mint: &self.prize_mint,
+                source_token_account: &self.vault_prize_token_account,
+                destination_token_account: &self.maker_prize_token_account,
+                prize_token_program: &self.prize_token_program,
+                associated_token_program: &self.associated_token_program,
+            }
+            .transfer(
+                &self.raffle_config.prize,
+                &self.vault.to_account_info(),
+                &self.maker.to_account_info(),
+                &self.maker.to_account_info(),
+                signer_seeds,
+            )?;
+        }
+
+        // Part of the creation fee is given back, as long as the treasury stays rent exempt
+        let refund_bps = self.config.policy.cancel_refund_bps.unwrap_or(0);
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let available = self.treasury
+            .lamports()
+            .saturating_sub(rent_exempt);
+
+        let fee_refund = RaffleConfig::bps_share(self.raffle_config.creation_fee, refund_bps as u64)?
+            .min(available);
+
+        if fee_refund > 0 {
+            let cpi_accounts = Transfer {
+                from: self.treasury.to_account_info(),
+                to: self.maker.to_account_info(),
+            };
+
+            let cpi_program = self.system_program.to_account_info();
+
+            let seeds = [b"treasury", house.as_ref(), &[self.config.treasury_bump]];
+            let signer_seeds = &[&seeds[..]][..];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                cpi_program,
+                cpi_accounts,
+                signer_seeds
+            );
+
+            transfer(cpi_ctx, fee_refund)?;
+        }
+
+        let raffle_config_seeds = &[
+            b"raffle",
+            house.as_ref(),
+            raffle.as_ref(),
+            &[self.raffle_config.raffle_config_bump],
+        ];
+
+        // The collection has no tickets, so we burn it and recover the rent
+        BurnCollectionV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+            .collection(&self.raffle.to_account_info())
+            .payer(&self.maker.to_account_info())
+            .authority(Some(&self.raffle_config.to_account_info()))
+            .invoke_signed(&[raffle_config_seeds])?;
+
+        self.raffle_config.close(self.maker.to_account_info())?;
+
+        Ok(RaffleCancelled {
+            house,
+            raffle,
+            maker: self.maker.key(),
+            tickets_sold: 0,
+            creation_fee_refund: fee_refund,
+            closed: true,
+            slot: clock.slot,
+        })
+    }
+}
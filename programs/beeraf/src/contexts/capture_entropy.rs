@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use solana_program::sysvar::slot_hashes;
+use mpl_core::accounts::BaseCollectionV1;
+
+use crate::{
+    error::BeeRafError, Config, EntropyCaptured, RaffleConfig, RaffleStatus, RandomnessSource, ENTROPY_DELAY,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CaptureEntropy<'info> {
+    // Anyone can capture the entropy once the raffle is over
+    pub signer: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(address = slot_hashes::ID)]
+    /// CHECK: This is checked by the address constraint
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+impl<'info> CaptureEntropy<'info> {
+    pub fn capture_entropy(&mut self) -> Result<EntropyCaptured> {
+        let clock = Clock::get()?;
+
+        // The slot hash must be unknown when the maker commits, so it is only captured once ticket sales are over
+        require!(self.raffle_config.sales_ended(&clock, self.raffle.num_minted), BeeRafError::StillOpen);
+        require!(
+            self.raffle_config.status != RaffleStatus::AwaitingRandomness,
+            BeeRafError::EntropyAlreadyCaptured
+        );
+        require!(
+            matches!(self.raffle_config.status, RaffleStatus::Open | RaffleStatus::SoldOut),
+            BeeRafError::InvalidStatus
+        );
+
+        // The first call picks a slot that is still in the future, so nobody (the maker
+        // included) can know its hash yet. Any later slot hash would be public when the
+        // transaction is built, and the maker could wait for one that favours them.
+        if self.raffle_config.entropy_slot == 0 {
+            // The seed must be committed before, legacy raffles commit it with commit_seed
+            require!(
+                self.raffle_config.randomness_source != RandomnessSource::CommitReveal
+                    || self.raffle_config.commitment != [0; 32],
+                BeeRafError::InvalidCommitment
+            );
+
+            self.raffle_config.entropy_slot = clock.slot
+                .checked_add(ENTROPY_DELAY)
+                .ok_or(BeeRafError::NumericalOverflow)?;
+
+            msg!("entropy slot: {}", self.raffle_config.entropy_slot);
+
+            return Ok(self.event(&clock));
+        }
+
+        // Nobody captured the hash while it was in SlotHashes, so a new slot is scheduled the same way.
+        // Anyone can schedule it, failing the raffle here would let them force refunds
+        if self.raffle_config.entropy_expired(&clock) {
+            self.raffle_config.entropy_slot = clock.slot
+                .checked_add(ENTROPY_DELAY)
+                .ok_or(BeeRafError::NumericalOverflow)?;
+
+            msg!("entropy slot: {}", self.raffle_config.entropy_slot);
+
+            return Ok(self.event(&clock));
+        }
+
+        let entropy_slot = self.raffle_config.entropy_slot;
+
+        // The hash of a slot is only in SlotHashes once the next slot is produced
+        require!(clock.slot > entropy_slot, BeeRafError::EntropyNotAvailable);
+
+        let data = self.slot_hashes.try_borrow_data()?;
+
+        self.raffle_config.entropy = slot_hash(&data, entropy_slot)?;
+        self.raffle_config.status = RaffleStatus::AwaitingRandomness;
+
+        Ok(self.event(&clock))
+    }
+
+    fn event(&self, clock: &Clock) -> EntropyCaptured {
+        EntropyCaptured {
+            house: self.house.key(),
+            raffle: self.raffle.key(),
+            entropy_slot: self.raffle_config.entropy_slot,
+            entropy: self.raffle_config.entropy,
+            status: self.raffle_config.status,
+            slot: clock.slot,
+        }
+    }
+}
+
+// SlotHashes is too big to deserialize, so we walk its entries: entries length (8 bytes),
+// then slot (8 bytes) and hash (32 bytes) of each entry, the most recent first.
+// Skipped slots have no entry, so it takes the first slot produced from `slot` on.
+fn slot_hash(data: &[u8], slot: u64) -> Result<[u8; 32]> {
+    require!(data.len() >= 8, BeeRafError::MissingEntropy);
+
+    let mut len_bytes = [0u8; 8];
+    len_bytes.copy_from_slice(&data[0..8]);
+
+    let entries = (u64::from_le_bytes(len_bytes) as usize).min((data.len() - 8) / 40);
+
+    let mut found: Option<[u8; 32]> = None;
+
+    for entry in data[8..8 + entries * 40].chunks_exact(40) {
+        let mut slot_bytes = [0u8; 8];
+        slot_bytes.copy_from_slice(&entry[0..8]);
+
+        let entry_slot = u64::from_le_bytes(slot_bytes);
+
+        // Older than the entropy slot, so the previous entry was the first one produced from it on
+        if entry_slot < slot {
+            return found.ok_or(BeeRafError::EntropyNotAvailable.into());
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&entry[8..40]);
+
+        if entry_slot == slot {
+            return Ok(hash);
+        }
+
+        found = Some(hash);
+    }
+
+    // Every entry is newer than the entropy slot, its hash has left the window
+    err!(BeeRafError::EntropyExpired)
+}
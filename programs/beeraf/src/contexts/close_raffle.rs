@@ -0,0 +1,224 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use mpl_core::{
+    accounts::BaseCollectionV1, instructions::{BurnCollectionV1CpiBuilder, UpdateCollectionV1CpiBuilder}, ID as MPL_CORE_ID
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::BeeRafError, payment::token_accounts, Config, Prize, RaffleClosed, RaffleConfig, RaffleStatus};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseRaffle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        close = maker,
+        constraint = raffle_config.authority == maker.key(),
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump = raffle_config.vault_bump
+    )]
+    vault: SystemAccount<'info>,
+
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub maker_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub vault_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(address = MPL_CORE_ID)]
+    /// CHECK: This is checked by the address constraint
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseRaffle<'info> {
+    pub fn close_raffle(&mut self) -> Result<RaffleClosed> {
+        let house = self.house.key();
+        let raffle = self.raffle.key();
+
+        let clock = Clock::get()?;
+
+        // The raffle can only be closed once the winners have been drawn,
+        // once it ended without selling the minimum of tickets, or once it has been cancelled
+        require!(
+            self.raffle_config.is_resolved()
+                || self.raffle_config.has_failed(&clock, self.raffle.num_minted)
+                || self.raffle_config.status == RaffleStatus::Cancelled,
+            BeeRafError::InvalidStatus
+        );
+
+        // The escrowed prize must have left the vault, see reclaim_prize
+        require!(
+            self.raffle_config.prize == Prize::None || self.raffle_config.prize_released,
+            BeeRafError::PrizeNotClaimed
+        );
+
+        let resolved = self.raffle_config.is_resolved();
+
+        // The house cut of a resolved raffle left the vault with the maker fees in solve_raffle
+        if resolved {
+            require!(self.raffle_config.escrowed_house_fees == 0, BeeRafError::InvalidStatus);
+        }
+
+        // Whatever is left in the vault is the unclaimed prize (or unclaimed refunds).
+        // We only sweep it once the claim period is over.
+        let leftover = self.raffle_config.pot
+            .checked_add(self.raffle_config.escrowed_fees)
+            .and_then(|leftover| leftover.checked_add(self.raffle_config.escrowed_house_fees))
+            .ok_or(BeeRafError::NumericalOverflow)?;
+
+        let token = token_accounts(
+            &self.raffle_config,
+            &self.vault.key(),
+            &self.payment_mint,
+            &self.vault_token_account,
+            &self.token_program,
+        )?;
+
+        // A resolved raffle gives the unclaimed prizes back to the maker. A failed or cancelled raffle
+        // only gives back the maker fees, the refunds nobody asked for and the house cut go to the treasury.
+        // The whole token balance is swept, the rounding dust goes with the pot
+        let dust = match &token {
+            Some(token) if leftover > 0 => token.vault_token_account.amount.saturating_sub(leftover),
+            _ => 0,
+        };
+
+        let (to_maker, to_treasury) = match resolved {
+            true => (leftover + dust, 0),
+            false => (
+                self.raffle_config.escrowed_fees,
+                leftover - self.raffle_config.escrowed_fees + dust,
+            ),
+        };
+
+        if leftover > 0 {
+            require!(self.raffle_config.claim_period_over(&clock)?, BeeRafError::PrizeNotClaimed);
+
+            let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
+            let signer_seeds = &[&seeds[..]][..];
+
+            let payouts = [
+                (self.maker.key(), &self.maker_token_account, self.maker.to_account_info(), to_maker),
+                (self.treasury.key(), &self.treasury_token_account, self.treasury.to_account_info(), to_treasury),
+            ];
+
+            for (owner, token_account, account, amount) in payouts {
+                if amount == 0 {
+                    continue;
+                }
+
+                match &token {
+                    Some(token) => {
+                        let token_account = token.owned_by(token_account, &owner)?;
+
+                        token.transfer(
+                            token.vault_token_account,
+                            token_account,
+                            &self.vault.to_account_info(),
+                            amount,
+                            signer_seeds,
+                        )?;
+                    }
+                    None => {
+                        let cpi_accounts = Transfer {
+                            from: self.vault.to_account_info(),
+                            to: account,
+                        };
+
+                        let cpi_program = self.system_program.to_account_info();
+
+                        let cpi_ctx = CpiContext::new_with_signer(
+                            cpi_program,
+                            cpi_accounts,
+                            signer_seeds
+                        );
+
+                        transfer(cpi_ctx, amount)?;
+                    }
+                }
+            }
+        }
+
+        let raffle_config_seeds = &[
+            b"raffle",
+            house.as_ref(),
+            raffle.as_ref(),
+            &[self.raffle_config.raffle_config_bump],
+        ];
+
+        let event = RaffleClosed {
+            house,
+            raffle,
+            maker: self.maker.key(),
+            status: self.raffle_config.status,
+            swept: to_maker,
+            swept_to_treasury: to_treasury,
+            collection_burned: self.raffle.current_size == 0,
+            slot: clock.slot,
+        };
+
+        if event.collection_burned {
+            // No tickets left in the collection, so we can burn it and recover the rent
+            BurnCollectionV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+                .collection(&self.raffle.to_account_info())
+                .payer(&self.maker.to_account_info())
+                .authority(Some(&self.raffle_config.to_account_info()))
+                .invoke_signed(&[raffle_config_seeds])?;
+        } else {
+            // Tickets are still alive, so the maker becomes the new update authority of the collection
+            UpdateCollectionV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+                .collection(&self.raffle.to_account_info())
+                .payer(&self.maker.to_account_info())
+                .authority(Some(&self.raffle_config.to_account_info()))
+                .new_update_authority(Some(&self.maker.to_account_info()))
+                .system_program(&self.system_program.to_account_info())
+                .invoke_signed(&[raffle_config_seeds])?;
+        }
+
+        Ok(event)
+    }
+}
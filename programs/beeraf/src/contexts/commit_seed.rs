@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use mpl_core::accounts::BaseCollectionV1;
+
+use crate::{error::BeeRafError, Config, RaffleConfig, RaffleStatus, RandomnessSource, SeedCommitted};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CommitSeed<'info> {
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        constraint = raffle_config.authority == maker.key(),
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+}
+
+impl<'info> CommitSeed<'info> {
+    pub fn commit_seed(&mut self, commitment: [u8; 32]) -> Result<SeedCommitted> {
+        require!(
+            self.raffle_config.randomness_source == RandomnessSource::CommitReveal,
+            BeeRafError::InvalidRandomnessSource
+        );
+        require!(commitment != [0; 32], BeeRafError::InvalidCommitment);
+
+        // Legacy raffles were created without a commitment, any other raffle keeps the one it was created with
+        require!(self.raffle_config.commitment == [0; 32], BeeRafError::CommitmentAlreadySet);
+        require!(
+            matches!(self.raffle_config.status, RaffleStatus::Open | RaffleStatus::SoldOut),
+            BeeRafError::InvalidStatus
+        );
+
+        // The seed must be fixed before the entropy slot is picked
+        require!(self.raffle_config.entropy_slot == 0, BeeRafError::CommitmentTooLate);
+
+        self.raffle_config.commitment = commitment;
+
+        Ok(SeedCommitted {
+            house: self.house.key(),
+            raffle: self.raffle.key(),
+            commitment,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
@@ -1,9 +1,15 @@
 use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{Mint, TokenInterface},
+};
 use mpl_core::{
     instructions::CreateCollectionV2CpiBuilder, types::{Attribute, Attributes, Plugin, PluginAuthority, PluginAuthorityPair}, ID as MPL_CORE_ID
 };
-use crate::{Config, RaffleConfig};
+use solana_program::clock::DEFAULT_MS_PER_SLOT;
+use crate::{error::BeeRafError, prize::PrizeAccounts, Config, Prize, RaffleConfig, RaffleCreated, RaffleEnd, RaffleStatus, RandomnessSource, RAFFLE_CONFIG_VERSION, MAX_CAPACITY, MAX_WINNERS, VAULT_VERSION};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateRaffle<'info> {
     #[account(mut)]
@@ -43,21 +49,187 @@ pub struct CreateRaffle<'info> {
     pub raffle_config: Account<'info, RaffleConfig>,
 
     #[account(
-        seeds = [b"vault", maker.key().as_ref()],
+        seeds = [b"vault", raffle.key().as_ref()],
         bump
     )]
     vault: SystemAccount<'info>,
 
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// CHECK: ATA of the vault for the payment mint, it is created by the associated token program
+    #[account(mut)]
+    pub vault_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of the treasury for the payment mint, it is created by the associated token program
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    // Only for raffles with an escrowed prize
+    /// CHECK: The mpl-core asset given as prize, it is checked against the args and by mpl-core
+    #[account(mut)]
+    pub prize_asset: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Collection of the prize asset, it is checked by mpl-core
+    #[account(mut)]
+    pub prize_collection: Option<UncheckedAccount<'info>>,
+
+    pub prize_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// CHECK: Token account of the maker holding the prize, it is checked by the token program
+    #[account(mut)]
+    pub maker_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of the vault for the prize mint, it is created by the associated token program
+    #[account(mut)]
+    pub vault_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    pub prize_token_program: Option<Interface<'info, TokenInterface>>,
+
     #[account(address = MPL_CORE_ID)]
     /// CHECK: This is checked by the address constraint
     pub mpl_core_program: UncheckedAccount<'info>,
 
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> CreateRaffle<'info> {
-    pub fn create_raffle(&mut self, args: CreateRaffleArgs, bumps: &CreateRaffleBumps) -> Result<()> {
-        let slot = Clock::get()?.slot + args.slot_interval; //+ 1_512_000;
+    pub fn create_raffle(&mut self, args: CreateRaffleArgs, bumps: &CreateRaffleBumps) -> Result<RaffleCreated> {
+        require!(
+            args.capacity > 0 && args.capacity <= MAX_CAPACITY,
+            BeeRafError::InvalidCapacity
+        );
+
+        let clock = Clock::get()?;
+
+        // The policy bounds the duration in slots, so timestamps are converted with the nominal slot time
+        let (slot, end_timestamp, slot_interval) = match args.end {
+            RaffleEnd::Slot { slot_interval } => (
+                clock.slot
+                    .checked_add(slot_interval)
+                    .ok_or(BeeRafError::NumericalOverflow)?,
+                0,
+                slot_interval,
+            ),
+            RaffleEnd::UnixTimestamp { end_timestamp } => {
+                require!(end_timestamp > clock.unix_timestamp, BeeRafError::InvalidDeadline);
+
+                let duration_ms = ((end_timestamp - clock.unix_timestamp) as u64)
+                    .checked_mul(1_000)
+                    .ok_or(BeeRafError::NumericalOverflow)?;
+
+                (0, end_timestamp, duration_ms / DEFAULT_MS_PER_SLOT)
+            }
+        };
+
+        let event = RaffleCreated {
+            house: self.house.key(),
+            maker: self.maker.key(),
+            raffle: self.raffle.key(),
+            raffle_config: self.raffle_config.key(),
+            args: args.clone(),
+            end_slot: slot,
+            creation_fee: self.config.fee,
+            slot: clock.slot,
+        };
+
+        let start_timestamp = args.start_timestamp.unwrap_or(0);
+
+        // Raffles ending at a slot are checked against the time it is expected at
+        let estimated_end = match end_timestamp {
+            0 => clock.unix_timestamp.saturating_add(
+                (slot_interval.saturating_mul(DEFAULT_MS_PER_SLOT) / 1_000) as i64
+            ),
+            end_timestamp => end_timestamp,
+        };
+
+        require!(start_timestamp < estimated_end, BeeRafError::InvalidDeadline);
+
+        self.config.policy.check_raffle(args.raffle_fee, args.ticket_price, slot_interval)?;
+
+        require!(
+            args.raffle_fee.saturating_add(self.config.house_fee_bps as u64) <= 10_000,
+            BeeRafError::InvalidFee
+        );
+
+        let min_tickets = args.min_tickets.unwrap_or(0);
+
+        require!(min_tickets <= args.capacity, BeeRafError::InvalidMinTickets);
+
+        require!(
+            args.prize_tiers.len() <= MAX_WINNERS
+                && args.prize_tiers.iter().all(|bps| *bps > 0)
+                && (args.prize_tiers.is_empty()
+                    || args.prize_tiers.iter().map(|bps| *bps as u32).sum::<u32>() == 10_000),
+            BeeRafError::InvalidPrizeTiers
+        );
+
+        match args.randomness_source {
+            RandomnessSource::CommitReveal => {
+                require!(args.commitment != [0; 32], BeeRafError::InvalidCommitment);
+            }
+            RandomnessSource::Vrf => {
+                require!(cfg!(feature = "vrf"), BeeRafError::InvalidRandomnessSource);
+            }
+        }
+
+        // Raffles paid with tokens get a token vault owned by the vault PDA, and the
+        // treasury a token account to receive the house cut when the raffle is resolved
+        if let Some(payment_mint) = args.payment_mint {
+            let mint = self.payment_mint.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let vault_token_account = self.vault_token_account.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let treasury_token_account = self.treasury_token_account.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let token_program = self.token_program.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let associated_token_program = self.associated_token_program.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+
+            require_keys_eq!(mint.key(), payment_mint, BeeRafError::InvalidPaymentMint);
+
+            for (associated_token, authority) in [
+                (vault_token_account.to_account_info(), self.vault.to_account_info()),
+                (treasury_token_account.to_account_info(), self.treasury.to_account_info()),
+            ] {
+                let cpi_accounts = Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token,
+                    authority,
+                    mint: mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new(associated_token_program.to_account_info(), cpi_accounts);
+
+                create_idempotent(cpi_ctx)?;
+            }
+        }
+
+        if let Prize::Token { amount, .. } = args.prize {
+            require!(amount > 0, BeeRafError::InvalidPrize);
+        }
+
+        // The prize is escrowed in the vault until a winner scratches the first place
+        let prize = PrizeAccounts {
+            mpl_core_program: &self.mpl_core_program.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+            prize_asset: &self.prize_asset,
+            prize_collection: &self.prize_collection,
+            prize_mint: &self.prize_mint,
+            source_token_account: &self.maker_prize_token_account,
+            destination_token_account: &self.vault_prize_token_account,
+            prize_token_program: &self.prize_token_program,
+            associated_token_program: &self.associated_token_program,
+        }
+        .transfer(
+            &args.prize,
+            &self.maker.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.maker.to_account_info(),
+            &[],
+        )?;
 
         // Add an Attribute Plugin that will hold the event details
         let mut collection_plugin: Vec<PluginAuthorityPair> = vec![];
@@ -65,7 +237,7 @@ impl<'info> CreateRaffle<'info> {
         let attribute_list: Vec<Attribute> = vec![
             Attribute {
                 key: "Capacity".to_string(),
-                value: 1000.to_string() // args.capacity.to_string(),
+                value: args.capacity.to_string(),
             },
         ];
         
@@ -94,6 +266,35 @@ impl<'info> CreateRaffle<'info> {
             ticket_price: args.ticket_price,
             raffle_config_bump: bumps.raffle_config,
             vault_bump: bumps.vault,
+            pot: 0,
+            vault_version: VAULT_VERSION,
+            capacity: args.capacity,
+            min_tickets,
+            escrowed_fees: 0,
+            resolved_slot: 0,
+            resolved_timestamp: 0,
+            commitment: args.commitment,
+            entropy: [0; 32],
+            entropy_slot: 0,
+            randomness_source: args.randomness_source,
+            randomness_account: Pubkey::default(),
+            prize_pool: 0,
+            claimed_places: 0,
+            prize_tiers: args.prize_tiers,
+            winners: vec![],
+            payment_mint: args.payment_mint,
+            refunded_tickets: 0,
+            prize,
+            prize_released: false,
+            tickets_sold: 0,
+            house_fee_bps: self.config.house_fee_bps,
+            escrowed_house_fees: 0,
+            end_timestamp,
+            start_timestamp,
+            end_on_sell_out: args.end_on_sell_out,
+            creation_fee: self.config.fee,
+            status: RaffleStatus::Open,
+            version: RAFFLE_CONFIG_VERSION,
         });
 
         let cpi_program = self.system_program.to_account_info();
@@ -107,16 +308,32 @@ impl<'info> CreateRaffle<'info> {
 
         transfer(cpi_ctx, self.config.fee)?;
         
-        Ok(())
+        Ok(event)
     }
     
 }
 
-#[derive(AnchorDeserialize, AnchorSerialize)]
+#[derive(AnchorDeserialize, AnchorSerialize, Clone)]
 pub struct CreateRaffleArgs {
     pub name: String,
     pub uri: String,
     pub ticket_price: u64,
     pub raffle_fee: u64,
-    pub slot_interval: u64,
+    // Slot interval or unix timestamp the raffle ends at
+    pub end: RaffleEnd,
+    // Unix timestamp before which tickets can not be bought (None to start right away)
+    pub start_timestamp: Option<i64>,
+    // Solve the raffle as soon as every ticket is sold instead of waiting for the deadline
+    pub end_on_sell_out: bool,
+    pub capacity: u32,
+    pub min_tickets: Option<u32>,
+    // sha256 of the secret seed revealed in solve_raffle
+    pub commitment: [u8; 32],
+    pub randomness_source: RandomnessSource,
+    // Basis points of the pot for each place, e.g. [5000, 3000, 2000] (empty for a single winner)
+    pub prize_tiers: Vec<u16>,
+    // SPL Token or Token-2022 mint to pay the tickets with (None for SOL)
+    pub payment_mint: Option<Pubkey>,
+    // Asset or tokens escrowed for the first place on top of the pot
+    pub prize: Prize,
 }
@@ -1,7 +1,8 @@
 use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL, system_program::{transfer, Transfer}};
 
-use crate::Config;
+use crate::{error::BeeRafError, Config, HouseInitialized, RafflePolicy};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
@@ -14,7 +15,7 @@ pub struct Initialize<'info> {
     treasury: SystemAccount<'info>,
 
     #[account(
-        init_if_needed,
+        init,
         payer = house,
         seeds = [b"config", treasury.key().as_ref()],
         space = Config::INIT_SPACE,
@@ -26,14 +27,28 @@ pub struct Initialize<'info> {
 }
 
 impl<'info> Initialize<'info> {
-    pub fn initialize(&mut self, fee: u64, bumps: &InitializeBumps) -> Result<()> {
+    pub fn initialize(&mut self, fee: u64, house_fee_bps: u16, policy: RafflePolicy, bumps: &InitializeBumps) -> Result<HouseInitialized> {
+        require!(house_fee_bps <= 10_000, BeeRafError::InvalidFee);
+
+        policy.validate()?;
+
         self.config.set_inner(Config {
             authority: self.house.key(),
             fee,
             config_bump: bumps.config,
             treasury_bump: bumps.treasury,
+            pending_authority: None,
+            house_fee_bps,
+            policy,
         });
 
-        Ok(())
+        Ok(HouseInitialized {
+            house: self.house.key(),
+            authority: self.house.key(),
+            fee,
+            house_fee_bps,
+            policy,
+            slot: Clock::get()?.slot,
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use mpl_core::{accounts::BaseCollectionV1, fetch_plugin, types::{Attributes, PluginType}};
+
+use crate::{error::BeeRafError, Config, RaffleConfig, RaffleMigrated, RaffleStatus, RAFFLE_CONFIG_VERSION, VAULT_VERSION};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    /// CHECK: Legacy accounts are smaller than the current layout, so it gets
+    /// reallocated before being deserialized in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump
+    )]
+    pub raffle_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", maker.key().as_ref()],
+        bump
+    )]
+    legacy_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump
+    )]
+    vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateVault<'info> {
+    pub fn migrate_vault(&mut self, bumps: &MigrateVaultBumps) -> Result<RaffleMigrated> {
+        let maker = self.maker.key();
+        let raffle_config_info = self.raffle_config.to_account_info();
+
+        // Make sure the account stays rent exempt with the current layout
+        let rent_exempt = Rent::get()?.minimum_balance(RaffleConfig::INIT_SPACE);
+        let missing_rent = rent_exempt.saturating_sub(raffle_config_info.lamports());
+
+        if missing_rent > 0 {
+            let cpi_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: raffle_config_info.clone(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+            transfer(cpi_ctx, missing_rent)?;
+        }
+
+        if raffle_config_info.data_len() < RaffleConfig::INIT_SPACE {
+            raffle_config_info.realloc(RaffleConfig::INIT_SPACE, true)?;
+        }
+
+        let mut raffle_config = RaffleConfig::try_deserialize(&mut &raffle_config_info.try_borrow_data()?[..])?;
+
+        require_keys_eq!(raffle_config.authority, maker, BeeRafError::InvalidMaker);
+        require!(raffle_config.vault_version == 0, BeeRafError::VaultAlreadyMigrated);
+
+        let (_, collection_attribute_list, _) = fetch_plugin::<BaseCollectionV1, Attributes>(
+            &self.raffle.to_account_info(),
+            PluginType::Attributes,
+        )?;
+
+        let attribute = |key: &str| -> Result<Option<u32>> {
+            collection_attribute_list
+                .attribute_list
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.parse::<u32>().map_err(|_| BeeRafError::NumericalOverflow.into()))
+                .transpose()
+        };
+
+        let winner = attribute("Winner")?;
+
+        // The maker vault is shared by every legacy raffle of the maker, so only the
+        // share of this raffle moves: every ticket paid the same amount into it.
+        // A legacy winner was paid the whole vault, so a resolved raffle has nothing left of its own
+        let pot = match winner {
+            Some(_) => 0,
+            None => {
+                let maker_fee = RaffleConfig::bps_share(raffle_config.ticket_price, raffle_config.raffle_fee)?;
+
+                raffle_config.ticket_price
+                    .checked_sub(maker_fee)
+                    .ok_or(BeeRafError::NumericalOverflow)?
+                    .checked_mul(self.raffle.num_minted as u64)
+                    .ok_or(BeeRafError::NumericalOverflow)?
+            }
+        };
+
+        // The maker vault can hold less than the share of the raffle once a winner of another
+        // raffle was paid from it, then the raffle keeps what is left. The vault is emptied
+        // rather than left below its rent exemption
+        let balance = self.legacy_vault.lamports();
+        let pot = match pot > 0 && balance.saturating_sub(pot) < Rent::get()?.minimum_balance(0) {
+            true => balance,
+            false => pot,
+        };
+
+        if pot > 0 {
+            let cpi_accounts = Transfer {
+                from: self.legacy_vault.to_account_info(),
+                to: self.vault.to_account_info(),
+            };
+
+            let cpi_program = self.system_program.to_account_info();
+
+            let seeds = [b"vault", maker.as_ref(), &[bumps.legacy_vault]];
+            let signer_seeds = &[&seeds[..]][..];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                cpi_program,
+                cpi_accounts,
+                signer_seeds
+            );
+
+            transfer(cpi_ctx, pot)?;
+        }
+
+        // Legacy raffles only stored the capacity in the collection attributes
+        if raffle_config.capacity == 0 {
+            raffle_config.capacity = attribute("Capacity")?.ok_or(BeeRafError::MissingAttribute)?;
+        }
+
+        // and the winner of a resolved raffle. Legacy winners were paid the whole old vault
+        // when the raffle was solved, so there is nothing left to claim
+        if raffle_config.version == 0 {
+            if let Some(winner) = winner {
+                raffle_config.winners = vec![winner];
+                raffle_config.claimed_places = 1;
+                raffle_config.status = RaffleStatus::Claimed;
+            }
+
+            // Legacy raffles were created without a house cut, so they keep selling without one
+            raffle_config.house_fee_bps = 0;
+
+            raffle_config.version = RAFFLE_CONFIG_VERSION;
+        }
+
+        raffle_config.pot = pot;
+        raffle_config.vault_bump = bumps.vault;
+        raffle_config.vault_version = VAULT_VERSION;
+
+        let mut data = raffle_config_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        raffle_config.try_serialize(&mut writer)?;
+
+        Ok(RaffleMigrated {
+            house: self.house.key(),
+            raffle: self.raffle.key(),
+            maker,
+            pot,
+            status: raffle_config.status,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
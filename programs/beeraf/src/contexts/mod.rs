@@ -12,3 +12,41 @@ pub use solve_raffle::*;
 
 pub mod scratch_ticket;
 pub use scratch_ticket::*;
+
+pub mod close_raffle;
+pub use close_raffle::*;
+
+pub mod withdraw_treasury;
+pub use withdraw_treasury::*;
+
+pub mod migrate_vault;
+pub use migrate_vault::*;
+
+pub mod refund_ticket;
+pub use refund_ticket::*;
+
+pub mod capture_entropy;
+pub use capture_entropy::*;
+
+pub mod commit_seed;
+pub use commit_seed::*;
+
+#[cfg(feature = "vrf")]
+pub mod request_randomness;
+#[cfg(feature = "vrf")]
+pub use request_randomness::*;
+
+pub mod reclaim_prize;
+pub use reclaim_prize::*;
+
+pub mod update_config;
+pub use update_config::*;
+
+pub mod propose_authority;
+pub use propose_authority::*;
+
+pub mod accept_authority;
+pub use accept_authority::*;
+
+pub mod cancel_raffle;
+pub use cancel_raffle::*;
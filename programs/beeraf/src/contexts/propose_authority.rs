@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{AuthorityProposed, Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> ProposeAuthority<'info> {
+    pub fn propose_authority(&mut self, new_authority: Pubkey) -> Result<AuthorityProposed> {
+        // The authority only changes once the new one accepts it
+        self.config.pending_authority = Some(new_authority);
+
+        Ok(AuthorityProposed {
+            house: self.house.key(),
+            authority: self.authority.key(),
+            pending_authority: new_authority,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenInterface}};
+use mpl_core::{accounts::BaseCollectionV1, ID as MPL_CORE_ID};
+
+use crate::{error::BeeRafError, prize::PrizeAccounts, Config, Prize, PrizeReclaimed, RaffleConfig, RaffleStatus};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimPrize<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        constraint = raffle_config.authority == maker.key(),
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump = raffle_config.vault_bump
+    )]
+    vault: SystemAccount<'info>,
+
+    /// CHECK: The mpl-core asset given as prize, it is checked against the raffle config
+    #[account(mut)]
+    pub prize_asset: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Collection of the prize asset, it is checked by mpl-core
+    #[account(mut)]
+    pub prize_collection: Option<UncheckedAccount<'info>>,
+
+    pub prize_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// CHECK: Token account of the vault holding the prize, it is checked by the token program
+    #[account(mut)]
+    pub vault_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of the maker for the prize mint, it is created by the associated token program
+    #[account(mut)]
+    pub maker_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    #[account(address = MPL_CORE_ID)]
+    /// CHECK: This is checked by the address constraint
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub prize_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReclaimPrize<'info> {
+    pub fn reclaim_prize(&mut self) -> Result<PrizeReclaimed> {
+        let raffle = self.raffle.key();
+
+        let clock = Clock::get()?;
+
+        require!(self.raffle_config.prize != Prize::None, BeeRafError::InvalidPrize);
+        require!(!self.raffle_config.prize_released, BeeRafError::PrizeAlreadyReleased);
+
+        // The raffle ended without selling the minimum of tickets
+        let failed = self.raffle_config.has_failed(&clock, self.raffle.num_minted);
+
+        // or the first place did not claim the prize in time
+        let unclaimed = self.raffle_config.is_resolved() && self.raffle_config.claim_period_over(&clock)?;
+
+        let cancelled = self.raffle_config.status == RaffleStatus::Cancelled;
+
+        require!(failed || unclaimed || cancelled, BeeRafError::PrizeNotReclaimable);
+
+        if failed && self.raffle_config.status != RaffleStatus::Refunding {
+            self.raffle_config.status = RaffleStatus::Refunding;
+            self.raffle_config.set_resolved(&clock);
+        }
+
+        let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
+        let signer_seeds = &[&seeds[..]][..];
+
+        PrizeAccounts {
+            mpl_core_program: &self.mpl_core_program.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+            prize_asset: &self.prize_asset,
+            prize_collection: &self.prize_collection,
+            prize_mint: &self.prize_mint,
+            source_token_account: &self.vault_prize_token_account,
+            destination_token_account: &self.maker_prize_token_account,
+            prize_token_program: &self.prize_token_program,
+            associated_token_program: &self.associated_token_program,
+        }
+        .transfer(
+            &self.raffle_config.prize,
+            &self.vault.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.maker.to_account_info(),
+            signer_seeds,
+        )?;
+
+        self.raffle_config.prize_released = true;
+
+        Ok(PrizeReclaimed {
+            house: self.house.key(),
+            raffle,
+            maker: self.maker.key(),
+            prize: self.raffle_config.prize,
+            status: self.raffle_config.status,
+            slot: clock.slot,
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use mpl_core::{accounts::{BaseAssetV1, BaseCollectionV1}, instructions::BurnV1CpiBuilder, types::UpdateAuthority, ID as MPL_CORE_ID};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::BeeRafError, payment::token_accounts, ticket_range, Config, RaffleConfig, RaffleStatus, TicketData, TicketRefunded};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefundTicket<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        constraint = ticket.owner == buyer.key(),
+        constraint = ticket.update_authority == UpdateAuthority::Collection(raffle.key()),
+    )]
+    pub ticket: Account<'info, BaseAssetV1>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump = raffle_config.vault_bump
+    )]
+    vault: SystemAccount<'info>,
+
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub buyer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub vault_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(address = MPL_CORE_ID)]
+    /// CHECK: This is checked by the address constraint
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundTicket<'info> {
+    pub fn refund_ticket(&mut self) -> Result<TicketRefunded> {
+        let house = self.house.key();
+        let raffle = self.raffle.key();
+
+        let clock = Clock::get()?;
+
+        // Refunds are only open once the raffle ended below the minimum of tickets,
+        // or when the maker cancelled it
+        let failed = self.raffle_config.has_failed(&clock, self.raffle.num_minted);
+
+        require!(
+            failed || self.raffle_config.status == RaffleStatus::Cancelled,
+            BeeRafError::RefundNotAvailable
+        );
+
+        if failed && self.raffle_config.status != RaffleStatus::Refunding {
+            self.raffle_config.status = RaffleStatus::Refunding;
+            self.raffle_config.set_resolved(&clock);
+        }
+
+        let (first, last) = ticket_range(&self.ticket.to_account_info())?;
+        let quantity = (last - first + 1) as u128;
+
+        // The buyer gets back an equal share of the pot, the escrowed maker fees and the
+        // escrowed house cut for each ticket number. For SOL raffles it is the ticket price,
+        // for tokens with a transfer fee it is what the vault actually received.
+        let outstanding = self.raffle_config.sold(self.raffle.num_minted)
+            .checked_sub(self.raffle_config.refunded_tickets)
+            .ok_or(BeeRafError::NumericalOverflow)? as u128;
+
+        require!(outstanding >= quantity, BeeRafError::RefundNotAvailable);
+
+        let share = |amount: u64| -> u64 {
+            (amount as u128 * quantity / outstanding) as u64
+        };
+
+        // The last refund takes whatever is left, so no dust stays in the vault
+        let (pot_share, fee_share, house_fee_share) = match outstanding == quantity {
+            true => (
+                self.raffle_config.pot,
+                self.raffle_config.escrowed_fees,
+                self.raffle_config.escrowed_house_fees,
+            ),
+            false => (
+                share(self.raffle_config.pot),
+                share(self.raffle_config.escrowed_fees),
+                share(self.raffle_config.escrowed_house_fees),
+            ),
+        };
+
+        self.raffle_config.pot -= pot_share;
+        self.raffle_config.escrowed_fees -= fee_share;
+        self.raffle_config.escrowed_house_fees -= house_fee_share;
+        self.raffle_config.refunded_tickets += quantity as u32;
+
+        let refund = pot_share
+            .checked_add(fee_share)
+            .and_then(|refund| refund.checked_add(house_fee_share))
+            .ok_or(BeeRafError::NumericalOverflow)?;
+
+        let raffle_config_seeds = &[
+            b"raffle",
+            house.as_ref(),
+            raffle.as_ref(),
+            &[self.raffle_config.raffle_config_bump],
+        ];
+
+        // Record the final ticket state, so indexers can see it before the ticket is burned
+        let mut ticket_data = TicketData::read(&self.ticket.to_account_info(), self.raffle_config.key())?;
+        ticket_data.refunded = true;
+        ticket_data.write(
+            &self.mpl_core_program.to_account_info(),
+            &self.ticket.to_account_info(),
+            &self.raffle.to_account_info(),
+            &self.raffle_config.to_account_info(),
+            &self.buyer.to_account_info(),
+            &self.system_program.to_account_info(),
+            &[raffle_config_seeds],
+        )?;
+
+        // Burn the ticket with the permanent burn delegate, the buyer recovers the rent
+        BurnV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+            .asset(&self.ticket.to_account_info())
+            .collection(Some(&self.raffle.to_account_info()))
+            .payer(&self.buyer.to_account_info())
+            .authority(Some(&self.raffle_config.to_account_info()))
+            .system_program(Some(&self.system_program.to_account_info()))
+            .invoke_signed(&[raffle_config_seeds])?;
+
+        let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
+        let signer_seeds = &[&seeds[..]][..];
+
+        let token = token_accounts(
+            &self.raffle_config,
+            &self.vault.key(),
+            &self.payment_mint,
+            &self.vault_token_account,
+            &self.token_program,
+        )?;
+
+        match token {
+            Some(token) => {
+                let buyer_token_account = token.owned_by(&self.buyer_token_account, &self.buyer.key())?;
+
+                token.transfer(
+                    token.vault_token_account,
+                    buyer_token_account,
+                    &self.vault.to_account_info(),
+                    refund,
+                    signer_seeds,
+                )?;
+            }
+            None => {
+                let cpi_accounts = Transfer {
+                    from: self.vault.to_account_info(),
+                    to: self.buyer.to_account_info(),
+                };
+
+                let cpi_program = self.system_program.to_account_info();
+
+                let cpi_ctx = CpiContext::new_with_signer(
+                    cpi_program,
+                    cpi_accounts,
+                    signer_seeds
+                );
+
+                transfer(cpi_ctx, refund)?;
+            }
+        }
+
+        Ok(TicketRefunded {
+            house,
+            raffle,
+            buyer: self.buyer.key(),
+            ticket: self.ticket.key(),
+            first_ticket: first,
+            last_ticket: last,
+            payment_mint: self.raffle_config.payment_mint,
+            refund,
+            house_fee: house_fee_share,
+            status: self.raffle_config.status,
+            slot: clock.slot,
+        })
+    }
+}
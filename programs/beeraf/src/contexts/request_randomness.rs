@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use mpl_core::accounts::BaseCollectionV1;
+
+use crate::{error::BeeRafError, vrf::read_randomness, Config, RaffleConfig, RaffleStatus, RandomnessRequested, RandomnessSource};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RequestRandomness<'info> {
+    pub maker: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = raffle.update_authority == raffle_config.key(),
+    )]
+    pub raffle: Account<'info, BaseCollectionV1>,
+
+    #[account(
+        mut,
+        constraint = raffle_config.authority == maker.key(),
+        seeds = [
+            b"raffle",
+            house.key().as_ref(),
+            raffle.key().as_ref(),
+        ],
+        bump = raffle_config.raffle_config_bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    /// CHECK: The owner and layout are checked when reading the randomness
+    pub randomness: UncheckedAccount<'info>,
+}
+
+impl<'info> RequestRandomness<'info> {
+    pub fn request_randomness(&mut self) -> Result<RandomnessRequested> {
+        let clock = Clock::get()?;
+
+        require!(
+            self.raffle_config.randomness_source == RandomnessSource::Vrf,
+            BeeRafError::InvalidRandomnessSource
+        );
+
+        // The randomness is bound once the raffle is over
+        require!(self.raffle_config.sales_ended(&clock, self.raffle.num_minted), BeeRafError::StillOpen);
+        require!(
+            self.raffle_config.randomness_account == Pubkey::default(),
+            BeeRafError::RandomnessAlreadyBound
+        );
+        require!(
+            matches!(self.raffle_config.status, RaffleStatus::Open | RaffleStatus::SoldOut),
+            BeeRafError::InvalidStatus
+        );
+
+        // Buyers may already be getting refunded
+        require!(!self.raffle_config.randomness_not_requested(&clock), BeeRafError::RandomnessTimedOut);
+
+        // An already fulfilled request would let the maker pick a known result
+        require!(
+            read_randomness(&self.randomness.to_account_info())?.is_none(),
+            BeeRafError::InvalidRandomnessAccount
+        );
+
+        // The raffle gets refunded if the request is not fulfilled in time
+        self.raffle_config.randomness_account = self.randomness.key();
+        self.raffle_config.entropy_slot = clock.slot;
+        self.raffle_config.status = RaffleStatus::AwaitingRandomness;
+
+        Ok(RandomnessRequested {
+            house: self.house.key(),
+            raffle: self.raffle.key(),
+            randomness: self.randomness.key(),
+            slot: clock.slot,
+        })
+    }
+}
@@ -1,7 +1,8 @@
 use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
-use mpl_core::{accounts::{BaseAssetV1, BaseCollectionV1}, fetch_plugin, types::{Attributes, PluginType, UpdateAuthority}, ID as MPL_CORE_ID};
+use mpl_core::{accounts::{BaseAssetV1, BaseCollectionV1}, instructions::BurnV1CpiBuilder, types::UpdateAuthority, ID as MPL_CORE_ID};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
 
-use crate::{error::BeeRafError, Config, RaffleConfig, WinnerEvent};
+use crate::{error::BeeRafError, payment::token_accounts, prize::PrizeAccounts, ticket_range, Config, Prize, PrizeClaimed, RaffleConfig, RaffleStatus, TicketData};
 
 #[event_cpi]
 #[derive(Accounts)]
@@ -29,6 +30,7 @@ pub struct ScratchTicket<'info> {
     pub config: Account<'info, Config>,
 
     #[account(
+        mut,
         seeds = [
             b"raffle",
             house.key().as_ref(), 
@@ -46,94 +48,228 @@ pub struct ScratchTicket<'info> {
     
     #[account(
         mut,
-        /* constraint = ticket.owner == buyer.key(),
+        constraint = ticket.owner == buyer.key(),
         constraint = ticket.update_authority == UpdateAuthority::Collection(raffle.key()),
- */    )]
-    pub ticket: Signer<'info/* , BaseAssetV1 */>,
+    )]
+    pub ticket: Account<'info, BaseAssetV1>,
     
     #[account(
         mut,
-        seeds = [b"vault", maker.key().as_ref()],
+        seeds = [b"vault", raffle.key().as_ref()],
         bump = raffle_config.vault_bump
     )]
     vault: SystemAccount<'info>,
 
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub buyer_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub vault_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Only for raffles with an escrowed prize
+    /// CHECK: The mpl-core asset given as prize, it is checked against the raffle config
+    #[account(mut)]
+    pub prize_asset: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Collection of the prize asset, it is checked by mpl-core
+    #[account(mut)]
+    pub prize_collection: Option<UncheckedAccount<'info>>,
+
+    pub prize_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// CHECK: Token account of the vault holding the prize, it is checked by the token program
+    #[account(mut)]
+    pub vault_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: ATA of the buyer for the prize mint, it is created by the associated token program
+    #[account(mut)]
+    pub buyer_prize_token_account: Option<UncheckedAccount<'info>>,
+
+    pub prize_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
     #[account(address = MPL_CORE_ID)]
     /// CHECK: This is checked by the address constraint
     pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> ScratchTicket<'info> {
-    pub fn scratch_ticket(&mut self) -> Result<(u32 , u32)> {
-        // Check that the maximum number of tickets has not been reached yet
-        let maker = self.maker.key();
-        
-        let (_, collection_attribute_list, _) = fetch_plugin::<BaseCollectionV1, Attributes>(
-            &self.raffle.to_account_info(),
-            PluginType::Attributes,
-        )?;
+    pub fn scratch_ticket(&mut self) -> Result<PrizeClaimed> {
+        let house = self.house.key();
+        let raffle = self.raffle.key();
 
-        // Search for the Winner attribute
-        let winner_attribute = collection_attribute_list
-        .attribute_list
-        .iter()
-        .find(|attr| attr.key == "Winner")
-        .ok_or(BeeRafError::MissingWinnerAttribute)?;
-
-        // Unwrap the Winner attribute value
-        let winner = winner_attribute
-        .value
-        .parse::<u32>()
-        .map_err(|_| BeeRafError::NumericalOverflow)?;
-
-        // Check that the maximum number of tickets has not been reached yet
-        let (_, ticket_attribute_list, _) = fetch_plugin::<BaseAssetV1, Attributes>(
-            &self.ticket.to_account_info(),
-            PluginType::Attributes,
-        )?;
+        // Legacy raffles get their winners from the Winner attribute in migrate_vault
+        require!(self.raffle_config.is_resolved(), BeeRafError::InvalidStatus);
+
+        let winners = self.raffle_config.winners.clone();
 
-        // Search for the Capacity attribute
-        let ticket_number_attribute = ticket_attribute_list
-        .attribute_list
-        .iter()
-        .find(|attr| attr.key == "Ticket Number")
-        .ok_or(BeeRafError::MissingWinnerAttribute)?;
-
-        // Unwrap the Winner attribute value
-        let ticket_number = ticket_number_attribute
-        .value
-        .parse::<u32>()
-        .map_err(|_| BeeRafError::NumericalOverflow)?;
-
-        msg!("ticket number: {}", ticket_number);
-        msg!("winner: {}", winner);
-
-        // you are the winner
-        if ticket_number == winner {
-            // We send the vault money to the winner
-            let cpi_accounts = Transfer {
-                from: self.vault.to_account_info(),
-                to: self.buyer.to_account_info(),
+        // The ticket holds every number from first to last
+        let (ticket_number, last_ticket_number) = ticket_range(&self.ticket.to_account_info())?;
+
+        msg!("ticket numbers: {}-{}", ticket_number, last_ticket_number);
+        msg!("winners: {:?}", winners);
+
+        // Places won by any of the numbers of the ticket
+        let places: Vec<usize> = winners
+            .iter()
+            .enumerate()
+            .filter(|(_, winner)| (ticket_number..=last_ticket_number).contains(*winner))
+            .map(|(place, _)| place)
+            .collect();
+
+        let mut claimed_amount = 0;
+        let mut prize_released = false;
+
+        // you are one of the winners
+        if !places.is_empty() {
+            for place in places.iter() {
+                let place_bit = 1u16 << place;
+
+                require!(self.raffle_config.claimed_places & place_bit == 0, BeeRafError::AlreadyClaimed);
+
+                self.raffle_config.claimed_places |= place_bit;
+            }
+
+            let all_places = (1u16 << winners.len()) - 1;
+            let last_claim = self.raffle_config.claimed_places == all_places;
+
+            // The last places to claim take whatever is left, so no dust stays in the vault
+            claimed_amount = match last_claim {
+                true => self.raffle_config.pot,
+                false => places
+                    .iter()
+                    .map(|place| self.raffle_config.prize_share(*place))
+                    .sum::<Result<u64>>()?,
             };
 
-            let cpi_program = self.system_program.to_account_info();
-    
-            let seeds = [b"vault", &self.maker.key().to_bytes()[..], &[self.raffle_config.vault_bump]];
+            // We send the prize of the place to the winner
+            let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
             let signer_seeds = &[&seeds[..]][..];
 
-            let cpi_ctx = CpiContext::new_with_signer(
-                cpi_program, 
-                cpi_accounts,
-                signer_seeds
-            );
-            
-            transfer(cpi_ctx, self.vault.to_account_info().lamports())?;
+            let token = token_accounts(
+                &self.raffle_config,
+                &self.vault.key(),
+                &self.payment_mint,
+                &self.vault_token_account,
+                &self.token_program,
+            )?;
+
+            match token {
+                Some(token) => {
+                    let buyer_token_account = token.owned_by(&self.buyer_token_account, &self.buyer.key())?;
+
+                    token.transfer(
+                        token.vault_token_account,
+                        buyer_token_account,
+                        &self.vault.to_account_info(),
+                        claimed_amount,
+                        signer_seeds,
+                    )?;
+                }
+                None => {
+                    let cpi_accounts = Transfer {
+                        from: self.vault.to_account_info(),
+                        to: self.buyer.to_account_info(),
+                    };
+
+                    let cpi_program = self.system_program.to_account_info();
+
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        cpi_program, 
+                        cpi_accounts,
+                        signer_seeds
+                    );
+                    
+                    transfer(cpi_ctx, claimed_amount)?;
+                }
+            }
+
+            self.raffle_config.pot = self.raffle_config.pot
+                .checked_sub(claimed_amount)
+                .ok_or(BeeRafError::NumericalOverflow)?;
+
+            if last_claim {
+                self.raffle_config.status = RaffleStatus::Claimed;
+            }
+
+            // The first place also takes the escrowed prize, unless the maker reclaimed it
+            // once the claim period was over. Its share of the pot is paid anyway
+            if places.contains(&0) && self.raffle_config.prize != Prize::None && !self.raffle_config.prize_released {
+                PrizeAccounts {
+                    mpl_core_program: &self.mpl_core_program.to_account_info(),
+                    system_program: &self.system_program.to_account_info(),
+                    prize_asset: &self.prize_asset,
+                    prize_collection: &self.prize_collection,
+                    prize_mint: &self.prize_mint,
+                    source_token_account: &self.vault_prize_token_account,
+                    destination_token_account: &self.buyer_prize_token_account,
+                    prize_token_program: &self.prize_token_program,
+                    associated_token_program: &self.associated_token_program,
+                }
+                .transfer(
+                    &self.raffle_config.prize,
+                    &self.vault.to_account_info(),
+                    &self.buyer.to_account_info(),
+                    &self.buyer.to_account_info(),
+                    signer_seeds,
+                )?;
+
+                self.raffle_config.prize_released = true;
+                prize_released = true;
+            }
         }
 
+        let raffle_config_seeds = &[
+            b"raffle",
+            house.as_ref(),
+            raffle.as_ref(),
+            &[self.raffle_config.raffle_config_bump],
+        ];
+
+        // Record the final ticket state, so indexers can see it before the ticket is burned
+        let mut ticket_data = TicketData::read(&self.ticket.to_account_info(), self.raffle_config.key())?;
+        ticket_data.scratched = true;
+        ticket_data.claimed_amount = claimed_amount;
+        ticket_data.write(
+            &self.mpl_core_program.to_account_info(),
+            &self.ticket.to_account_info(),
+            &self.raffle.to_account_info(),
+            &self.raffle_config.to_account_info(),
+            &self.buyer.to_account_info(),
+            &self.system_program.to_account_info(),
+            &[raffle_config_seeds],
+        )?;
+
+        // Burn the scratched ticket with the permanent burn delegate, the buyer recovers the rent
+        BurnV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+            .asset(&self.ticket.to_account_info())
+            .collection(Some(&self.raffle.to_account_info()))
+            .payer(&self.buyer.to_account_info())
+            .authority(Some(&self.raffle_config.to_account_info()))
+            .system_program(Some(&self.system_program.to_account_info()))
+            .invoke_signed(&[raffle_config_seeds])?;
 
-        Ok((winner, ticket_number))
+        Ok(PrizeClaimed {
+            house,
+            raffle,
+            buyer: self.buyer.key(),
+            ticket: self.ticket.key(),
+            winner: winners[0],
+            first_ticket: ticket_number,
+            last_ticket: last_ticket_number,
+            payment_mint: self.raffle_config.payment_mint,
+            claimed_amount,
+            prize_released,
+            slot: Clock::get()?.slot,
+        })
     }
     
 }
\ No newline at end of file
@@ -1,10 +1,14 @@
-use anchor_lang::prelude::*;
-use solana_program::{sysvar::instructions::load_instruction_at_checked, ed25519_program, hash::hash};
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use solana_program::{sysvar::instructions::load_instruction_at_checked, ed25519_program, hash::{hash, hashv}};
 use mpl_core::{ accounts::BaseCollectionV1, fetch_plugin, instructions::{UpdateCollectionPluginV1, UpdateCollectionPluginV1Cpi, UpdateCollectionPluginV1CpiBuilder}, types::{Attribute, Attributes, PluginType}, ID as MPL_CORE_ID };
 use anchor_instruction_sysvar::Ed25519InstructionSignatures;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use crate::{error::BeeRafError, Config, RafEvent, RaffleConfig};
+use crate::{error::BeeRafError, payment::token_accounts, Config, RaffleConfig, RaffleResolved, RaffleStatus, RandomnessSource};
+#[cfg(feature = "vrf")]
+use crate::vrf::read_randomness;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct SolveRaffle<'info> {
     #[account(mut)]
@@ -14,6 +18,7 @@ pub struct SolveRaffle<'info> {
     pub house: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         seeds = [b"treasury", house.key().as_ref()],
         bump = config.treasury_bump
     )]
@@ -32,6 +37,8 @@ pub struct SolveRaffle<'info> {
     pub raffle: Account<'info, BaseCollectionV1>,
 
     #[account(
+        mut,
+        constraint = raffle_config.authority == maker.key(),
         seeds = [
             b"raffle",
             house.key().as_ref(), 
@@ -41,12 +48,36 @@ pub struct SolveRaffle<'info> {
     )]
     pub raffle_config: Account<'info, RaffleConfig>,
 
+    #[account(
+        mut,
+        seeds = [b"vault", raffle.key().as_ref()],
+        bump = raffle_config.vault_bump
+    )]
+    vault: SystemAccount<'info>,
+
     #[account(address = MPL_CORE_ID)]
     /// CHECK: This is checked by the address constraint
     pub mpl_core_program: UncheckedAccount<'info>,
 
     /// CHECK: This is safe
     pub instruction_sysvar: AccountInfo<'info>,
+
+    /// CHECK: Only used by VRF raffles, it must be the randomness account bound in request_randomness
+    pub randomness: Option<UncheckedAccount<'info>>,
+
+    // Only for raffles paid with tokens
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub maker_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub vault_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
@@ -83,35 +114,128 @@ impl<'info> SolveRaffle<'info> {
         Ok(())
     }
     
-    pub fn solve_raffle(&mut self, sig: &[u8]) -> Result<()> {
-        let house = self.house.key();
-        let raffle = self.raffle.key();
+    pub fn solve_raffle(&mut self, sig: &[u8], seed: &[u8; 32]) -> Result<RaffleResolved> {
+        require!(
+            self.raffle_config.randomness_source == RandomnessSource::CommitReveal,
+            BeeRafError::InvalidRandomnessSource
+        );
+
+        self.check_resolvable()?;
+
+        require!(self.raffle_config.status == RaffleStatus::AwaitingRandomness, BeeRafError::MissingEntropy);
+
+        // Legacy raffles were created without a commitment, they get one with commit_seed
+        require!(self.raffle_config.commitment != [0; 32], BeeRafError::InvalidCommitment);
+        require!(
+            hash(seed).to_bytes() == self.raffle_config.commitment,
+            BeeRafError::InvalidReveal
+        );
+
+        // The seed was committed before any ticket was sold and the entropy
+        // was captured after the deadline, so nobody could know the roll in advance
+        let hash = hashv(&[sig, seed, &self.raffle_config.entropy]).to_bytes();
+
+        self.resolve(*seed, hash)
+    }
+
+    #[cfg(feature = "vrf")]
+    pub fn solve_raffle_vrf(&mut self) -> Result<RaffleResolved> {
+        require!(
+            self.raffle_config.randomness_source == RandomnessSource::Vrf,
+            BeeRafError::InvalidRandomnessSource
+        );
+
+        self.check_resolvable()?;
+
+        let randomness_account = self.randomness
+            .as_ref()
+            .ok_or(BeeRafError::InvalidRandomnessAccount)?;
+
+        require_keys_eq!(
+            randomness_account.key(),
+            self.raffle_config.randomness_account,
+            BeeRafError::InvalidRandomnessAccount
+        );
+
+        let randomness = read_randomness(&randomness_account.to_account_info())?
+            .ok_or(BeeRafError::RandomnessNotFulfilled)?;
+
+        let hash = hash(&randomness).to_bytes();
 
-        let slot = Clock::get()?.slot;
+        self.resolve(hash, hash)
+    }
+
+    fn check_resolvable(&self) -> Result<()> {
+        let clock = Clock::get()?;
         
-        require!(slot > self.raffle_config.slot, BeeRafError::StillOpen);
+        require!(self.raffle_config.sales_ended(&clock, self.raffle.num_minted), BeeRafError::StillOpen);
+
+        require!(self.raffle_config.status != RaffleStatus::Cancelled, BeeRafError::RaffleCancelled);
+
+        require!(self.raffle_config.is_unresolved(), BeeRafError::AlreadyResolved);
+
+        let sold = self.raffle_config.sold(self.raffle.num_minted);
 
         require!(
-            self.raffle.num_minted > 0,
+            sold > 0,
             BeeRafError::NoSoldAnyTicket
         );
 
-        let hash = hash(sig).to_bytes();
-        let mut hash_16: [u8;16] = [0;16];
-        hash_16.copy_from_slice(&hash[0..16]);
-        let lower = u128::from_le_bytes(hash_16);
-        hash_16.copy_from_slice(&hash[16..32]);
-        let upper = u128::from_le_bytes(hash_16);
-        
-        let roll = lower
-            .wrapping_add(upper)
-            .wrapping_rem(self.raffle.num_minted as u128) as u32 + 1;
+        require!(
+            sold >= self.raffle_config.min_tickets,
+            BeeRafError::MinTicketsNotReached
+        );
+
+        // Buyers may already be getting refunded
+        require!(!self.raffle_config.randomness_timed_out(&clock), BeeRafError::RandomnessTimedOut);
+
+        Ok(())
+    }
+
+    fn resolve(&mut self, seed: [u8; 32], hash: [u8; 32]) -> Result<RaffleResolved> {
+        let house = self.house.key();
+        let raffle = self.raffle.key();
+
+        let clock = Clock::get()?;
+
+        // Ticket numbers go from 1 to the tickets sold, a ticket asset can hold several of them
+        let num_minted = self.raffle_config.sold(self.raffle.num_minted);
+        let places = self.raffle_config.tiers().len().min(num_minted as usize);
+
+        // Draw one distinct ticket number per place. The first place uses the
+        // seed itself and the next ones a hash of the seed with the place.
+        let mut winners: Vec<u32> = Vec::with_capacity(places);
+
+        for place in 0..places {
+            let place_hash = match place {
+                0 => hash,
+                _ => hashv(&[&hash, &(place as u32).to_le_bytes()]).to_bytes(),
+            };
 
-        emit!(RafEvent {
-            winner: roll,
-        });
+            let mut roll = Self::roll(&place_hash, num_minted);
 
-        require!(roll > 0 && roll < self.raffle.num_minted +1, BeeRafError::FailedRoll);
+            // A number can only win once, so we move to the next free ticket
+            while winners.contains(&roll) {
+                roll = roll % num_minted + 1;
+            }
+
+            require!(roll > 0 && roll < num_minted + 1, BeeRafError::FailedRoll);
+
+            winners.push(roll);
+        }
+
+        let event = RaffleResolved {
+            house,
+            raffle,
+            randomness_source: self.raffle_config.randomness_source,
+            seed,
+            entropy: self.raffle_config.entropy,
+            roll: hash,
+            winners: winners.clone(),
+            tickets_sold: num_minted,
+            prize_pool: self.raffle_config.pot,
+            slot: clock.slot,
+        };
 
         // Check that the maximum number of tickets has not been reached yet
         let (_,mut collection_attribute_list, _) = fetch_plugin::<BaseCollectionV1, Attributes>(
@@ -119,12 +243,17 @@ impl<'info> SolveRaffle<'info> {
             PluginType::Attributes,
         )?;
 
-        let winner = Attribute {
-            key: "Winner".to_string(),
-            value: roll.to_string() // args.capacity.to_string(),
-        };
+        for (place, roll) in winners.iter().enumerate() {
+            let key = match place {
+                0 => "Winner".to_string(),
+                _ => format!("Winner {}", place + 1),
+            };
 
-        collection_attribute_list.attribute_list.push(winner);
+            collection_attribute_list.attribute_list.push(Attribute {
+                key,
+                value: roll.to_string(),
+            });
+        }
         
         // Prepare seeds for the PDA `raffle_config`
         let raffle_config_seeds = &[
@@ -148,6 +277,81 @@ impl<'info> SolveRaffle<'info> {
             .invoke_signed(&[raffle_config_seeds])
             .unwrap();
 
-        Ok(())
+        self.raffle_config.winners = winners;
+        self.raffle_config.prize_pool = self.raffle_config.pot;
+        self.raffle_config.status = RaffleStatus::Resolved;
+        self.raffle_config.set_resolved(&clock);
+
+        // The raffle went through, so the maker gets the escrowed fees and the house its cut
+        if self.raffle_config.escrowed_fees > 0 || self.raffle_config.escrowed_house_fees > 0 {
+            let seeds = [b"vault", raffle.as_ref(), &[self.raffle_config.vault_bump]];
+            let signer_seeds = &[&seeds[..]][..];
+
+            let token = token_accounts(
+                &self.raffle_config,
+                &self.vault.key(),
+                &self.payment_mint,
+                &self.vault_token_account,
+                &self.token_program,
+            )?;
+
+            let payouts = [
+                (self.maker.key(), &self.maker_token_account, self.maker.to_account_info(), self.raffle_config.escrowed_fees),
+                (self.treasury.key(), &self.treasury_token_account, self.treasury.to_account_info(), self.raffle_config.escrowed_house_fees),
+            ];
+
+            for (owner, token_account, account, amount) in payouts {
+                if amount == 0 {
+                    continue;
+                }
+
+                match &token {
+                    Some(token) => {
+                        let token_account = token.owned_by(token_account, &owner)?;
+
+                        token.transfer(
+                            token.vault_token_account,
+                            token_account,
+                            &self.vault.to_account_info(),
+                            amount,
+                            signer_seeds,
+                        )?;
+                    }
+                    None => {
+                        let cpi_accounts = Transfer {
+                            from: self.vault.to_account_info(),
+                            to: account,
+                        };
+
+                        let cpi_program = self.system_program.to_account_info();
+
+                        let cpi_ctx = CpiContext::new_with_signer(
+                            cpi_program,
+                            cpi_accounts,
+                            signer_seeds
+                        );
+
+                        transfer(cpi_ctx, amount)?;
+                    }
+                }
+            }
+
+            self.raffle_config.escrowed_fees = 0;
+            self.raffle_config.escrowed_house_fees = 0;
+        }
+
+        Ok(event)
     }
-}
\ No newline at end of file
+
+    fn roll(hash: &[u8; 32], num_minted: u32) -> u32 {
+        let mut hash_16: [u8;16] = [0;16];
+        hash_16.copy_from_slice(&hash[0..16]);
+        let lower = u128::from_le_bytes(hash_16);
+        hash_16.copy_from_slice(&hash[16..32]);
+        let upper = u128::from_le_bytes(hash_16);
+        
+        lower
+            .wrapping_add(upper)
+            .wrapping_rem(num_minted as u128) as u32 + 1
+    }
+}
@@ -0,0 +1,99 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::BeeRafError, Config, ConfigUpdated, RafflePolicy};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"treasury", house.key().as_ref()],
+        bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    /// CHECK: Configs created before the current layout are smaller, so it gets
+    /// reallocated before being deserialized in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"config", treasury.key().as_ref()],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(&mut self, args: UpdateConfigArgs) -> Result<ConfigUpdated> {
+        let config_info = self.config.to_account_info();
+
+        // Make sure the account stays rent exempt with the current layout
+        let rent_exempt = Rent::get()?.minimum_balance(Config::INIT_SPACE);
+        let missing_rent = rent_exempt.saturating_sub(config_info.lamports());
+
+        if missing_rent > 0 {
+            let cpi_accounts = Transfer {
+                from: self.authority.to_account_info(),
+                to: config_info.clone(),
+            };
+
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+            transfer(cpi_ctx, missing_rent)?;
+        }
+
+        if config_info.data_len() < Config::INIT_SPACE {
+            config_info.realloc(Config::INIT_SPACE, true)?;
+        }
+
+        let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+
+        require_keys_eq!(config.authority, self.authority.key(), BeeRafError::InvalidAuthority);
+
+        if let Some(fee) = args.fee {
+            config.fee = fee;
+        }
+
+        if let Some(house_fee_bps) = args.house_fee_bps {
+            require!(house_fee_bps <= 10_000, BeeRafError::InvalidFee);
+
+            config.house_fee_bps = house_fee_bps;
+        }
+
+        if let Some(policy) = args.policy {
+            policy.validate()?;
+
+            config.policy = policy;
+        }
+
+        let mut data = config_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        config.try_serialize(&mut writer)?;
+
+        Ok(ConfigUpdated {
+            house: self.house.key(),
+            authority: config.authority,
+            fee: config.fee,
+            house_fee_bps: config.house_fee_bps,
+            policy: config.policy,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize)]
+pub struct UpdateConfigArgs {
+    // Fee charged to the maker for each raffle created (None keeps the current one)
+    pub fee: Option<u64>,
+    // House cut of every ticket sale, in basis points (None keeps the current one)
+    pub house_fee_bps: Option<u16>,
+    // Bounds of the raffles created from now on (None keeps the current ones)
+    pub policy: Option<RafflePolicy>,
+}
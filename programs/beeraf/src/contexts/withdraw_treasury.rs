@@ -0,0 +1,120 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{error::BeeRafError, Config, TreasuryWithdrawn};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: We don't make anything on this account
+    pub house: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", house.key().as_ref()],
+        bump = config.treasury_bump
+    )]
+    treasury: SystemAccount<'info>,
+
+    #[account(
+        has_one = authority,
+        seeds = [b"config", treasury.key().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+
+    // Only to withdraw the house fees of raffles paid with tokens
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(mut)]
+    pub destination_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawTreasury<'info> {
+    pub fn withdraw_treasury(&mut self, amount: u64) -> Result<TreasuryWithdrawn> {
+        let house = self.house.key();
+
+        let seeds = [b"treasury", house.as_ref(), &[self.config.treasury_bump]];
+        let signer_seeds = &[&seeds[..]][..];
+
+        // Tokens collected from the house fee of raffles paid with tokens
+        if let Some(mint) = &self.mint {
+            let treasury_token_account = self.treasury_token_account.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let destination_token_account = self.destination_token_account.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+            let token_program = self.token_program.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+
+            require_keys_eq!(treasury_token_account.mint, mint.key(), BeeRafError::InvalidTokenAccount);
+            require_keys_eq!(treasury_token_account.owner, self.treasury.key(), BeeRafError::InvalidTokenAccount);
+            require_keys_eq!(destination_token_account.mint, mint.key(), BeeRafError::InvalidTokenAccount);
+
+            require!(amount <= treasury_token_account.amount, BeeRafError::InsufficientTreasuryFunds);
+
+            let cpi_accounts = TransferChecked {
+                from: treasury_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to: destination_token_account.to_account_info(),
+                authority: self.treasury.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds
+            );
+
+            transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+            return Ok(TreasuryWithdrawn {
+                house,
+                amount,
+                destination: destination_token_account.key(),
+                mint: Some(mint.key()),
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        // The treasury must always keep enough lamports to stay rent exempt
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let available = self.treasury
+            .lamports()
+            .saturating_sub(rent_exempt);
+
+        require!(amount <= available, BeeRafError::InsufficientTreasuryFunds);
+
+        let cpi_accounts = Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.destination.to_account_info(),
+        };
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            cpi_program,
+            cpi_accounts,
+            signer_seeds
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(TreasuryWithdrawn {
+            house,
+            amount,
+            destination: self.destination.key(),
+            mint: None,
+            slot: Clock::get()?.slot,
+        })
+    }
+}
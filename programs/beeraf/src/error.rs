@@ -49,4 +49,145 @@ pub enum BeeRafError {
 
     #[msg("Raffle time has expired")]
     TimeExpired,
+
+    #[msg("Prize has not been claimed yet")]
+    PrizeNotClaimed,
+
+    #[msg("Not enough funds in the treasury")]
+    InsufficientTreasuryFunds,
+
+    #[msg("Signer is not the raffle maker")]
+    InvalidMaker,
+
+    #[msg("Raffle vault has already been migrated")]
+    VaultAlreadyMigrated,
+
+    #[msg("Capacity must be between 1 and the maximum capacity")]
+    InvalidCapacity,
+
+    #[msg("Minimum of tickets can not be greater than the capacity")]
+    InvalidMinTickets,
+
+    #[msg("Raffle did not reach the minimum of tickets")]
+    MinTicketsNotReached,
+
+    #[msg("Refunds are only available when the raffle ends below the minimum of tickets")]
+    RefundNotAvailable,
+
+    #[msg("Prize has already been claimed")]
+    AlreadyClaimed,
+
+    #[msg("Ticket data is invalid")]
+    InvalidTicketData,
+
+    #[msg("Commitment can not be empty")]
+    InvalidCommitment,
+
+    #[msg("Revealed seed does not match the commitment")]
+    InvalidReveal,
+
+    #[msg("Entropy has not been captured yet")]
+    MissingEntropy,
+
+    #[msg("Entropy has already been captured")]
+    EntropyAlreadyCaptured,
+
+    #[msg("Entropy slot has not been produced yet")]
+    EntropyNotAvailable,
+
+    #[msg("Entropy slot has left the SlotHashes window")]
+    EntropyExpired,
+
+    #[msg("Raffle already has a commitment")]
+    CommitmentAlreadySet,
+
+    #[msg("Seed must be committed before the entropy slot is scheduled")]
+    CommitmentTooLate,
+
+    #[msg("Randomness was not revealed in time, the raffle can only be refunded")]
+    RandomnessTimedOut,
+
+    #[msg("Randomness source is not available for this raffle")]
+    InvalidRandomnessSource,
+
+    #[msg("Randomness account is invalid")]
+    InvalidRandomnessAccount,
+
+    #[msg("Randomness has not been fulfilled yet")]
+    RandomnessNotFulfilled,
+
+    #[msg("Randomness account has already been bound")]
+    RandomnessAlreadyBound,
+
+    #[msg("Prize tiers must be at most the maximum of winners and add up to 10000 basis points")]
+    InvalidPrizeTiers,
+
+    #[msg("Raffle has already been resolved")]
+    AlreadyResolved,
+
+    #[msg("Token accounts are required for raffles paid with tokens")]
+    MissingTokenAccounts,
+
+    #[msg("Payment mint does not match the raffle")]
+    InvalidPaymentMint,
+
+    #[msg("Token account is invalid")]
+    InvalidTokenAccount,
+
+    #[msg("Prize accounts are required for raffles with an escrowed prize")]
+    MissingPrizeAccounts,
+
+    #[msg("Prize does not match the raffle")]
+    InvalidPrize,
+
+    #[msg("Prize has already been released")]
+    PrizeAlreadyReleased,
+
+    #[msg("Prize can only be reclaimed when the raffle failed or the claim period is over")]
+    PrizeNotReclaimable,
+
+    #[msg("Quantity of tickets must be greater than zero")]
+    InvalidQuantity,
+
+    #[msg("Signer is not the house authority")]
+    InvalidAuthority,
+
+    #[msg("Maker and house fees can not exceed 10000 basis points")]
+    InvalidFee,
+
+    #[msg("Raffle policy bounds are invalid")]
+    InvalidPolicy,
+
+    #[msg("Raffle fee is above the maximum of the house")]
+    RaffleFeeTooHigh,
+
+    #[msg("Ticket price is below the minimum of the house")]
+    TicketPriceTooLow,
+
+    #[msg("Ticket price is above the maximum of the house")]
+    TicketPriceTooHigh,
+
+    #[msg("Slot interval is below the minimum of the house")]
+    SlotIntervalTooShort,
+
+    #[msg("Slot interval is above the maximum of the house")]
+    SlotIntervalTooLong,
+
+    #[msg("Raffle has not started yet")]
+    NotStarted,
+
+    #[msg("Raffle must end in the future and after it starts")]
+    InvalidDeadline,
+
+    #[msg("Raffle has been cancelled")]
+    RaffleCancelled,
+
+    #[msg("Raffle can only be cancelled before ticket sales are over")]
+    CancelNotAvailable,
+
+    #[msg("Raffle is not open for ticket sales")]
+    RaffleNotOpen,
+
+    #[msg("Raffle status does not allow this instruction")]
+    InvalidStatus,
 }
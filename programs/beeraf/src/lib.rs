@@ -1,7 +1,11 @@
 pub mod constants;
 pub mod contexts;
 pub mod error;
+pub mod payment;
+pub mod prize;
 pub mod state;
+#[cfg(feature = "vrf")]
+pub mod vrf;
 
 use anchor_lang::prelude::*;
 
@@ -18,8 +22,19 @@ pub mod beeraf {
     // It will create the treasoury account where the fees will go.
     // Setup the fee amount it will get
     // authority person who can take out funds
-    pub fn initialize(ctx: Context<Initialize>, fee: u64) -> Result<()> {
-        ctx.accounts.initialize(fee, &ctx.bumps)
+    // House cut in basis points of every ticket sold
+    // Policy with the bounds of the raffles
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        fee: u64,
+        house_fee_bps: u16,
+        policy: RafflePolicy
+    ) -> Result<()> {
+        let event = ctx.accounts.initialize(fee, house_fee_bps, policy, &ctx.bumps)?;
+
+        emit_cpi!(event);
+
+        Ok(())
     }
 
     // Create_raffle will create a PDA where contains the amount required to buy a ticket
@@ -30,7 +45,11 @@ pub mod beeraf {
         ctx: Context<CreateRaffle>,
         args: CreateRaffleArgs
     ) -> Result<()> {
-        ctx.accounts.create_raffle(args, &ctx.bumps)
+        let event = ctx.accounts.create_raffle(args, &ctx.bumps)?;
+
+        emit_cpi!(event);
+
+        Ok(())
     }
 
     // It will pay the amount referenced in the PDA
@@ -39,17 +58,87 @@ pub mod beeraf {
         ctx: Context<BuyTicket>,
         args: BuyTicketArgs
     ) -> Result<()> {
-        ctx.accounts.buy_ticket(args)
+        let event = ctx.accounts.buy_ticket(args)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // Legacy raffles were created without a commitment
+    // the maker commits the seed before the entropy gets captured
+    pub fn commit_seed(
+        ctx: Context<CommitSeed>,
+        commitment: [u8; 32]
+    ) -> Result<()> {
+        let event = ctx.accounts.commit_seed(commitment)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // Once the raffle is over, anyone can schedule a future slot and then capture its hash
+    // it will be mixed with the seed the maker reveals
+    pub fn capture_entropy(
+        ctx: Context<CaptureEntropy>
+    ) -> Result<()> {
+        let event = ctx.accounts.capture_entropy()?;
+
+        emit_cpi!(event);
+
+        Ok(())
     }
 
     // It will generate a valid number considering the amount of tickets
     // It will get stored in the PDA and change the status to RESOLVED
     pub fn solve_raffle(
         ctx: Context<SolveRaffle>,
-        sig: Vec<u8>
+        sig: Vec<u8>,
+        seed: [u8; 32]
     ) -> Result<()> {
         ctx.accounts.verify_ed25519_signature(&sig)?;
-        ctx.accounts.solve_raffle(&sig)
+        let event = ctx.accounts.solve_raffle(&sig, &seed)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // VRF raffles bind a randomness account once the raffle is over
+    #[cfg(feature = "vrf")]
+    pub fn request_randomness(
+        ctx: Context<RequestRandomness>
+    ) -> Result<()> {
+        let event = ctx.accounts.request_randomness()?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // It will generate the winner number from the randomness revealed by the oracle
+    #[cfg(feature = "vrf")]
+    pub fn solve_raffle_vrf(
+        ctx: Context<SolveRaffle>
+    ) -> Result<()> {
+        let event = ctx.accounts.solve_raffle_vrf()?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // If the raffle ended below the minimum of tickets
+    // it will burn the ticket and give back what the buyer paid
+    pub fn refund_ticket(
+        ctx: Context<RefundTicket>
+    ) -> Result<()> {
+        let event = ctx.accounts.refund_ticket()?;
+
+        emit_cpi!(event);
+
+        Ok(())
     }
 
     // IT will check if the ticket is the winner
@@ -58,15 +147,108 @@ pub mod beeraf {
     pub fn scratch_ticket(
         ctx: Context<ScratchTicket>
     ) -> Result<()> {
-        let (winner, ticket) = ctx.accounts.scratch_ticket()?;
+        let event = ctx.accounts.scratch_ticket()?;
     
-        emit_cpi!(WinnerEvent {
-            winner, ticket
-        });
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // The maker backs out of a raffle. Without tickets it closes the raffle
+    // and gives back part of the fee, otherwise holders can get refunded
+    pub fn cancel_raffle(
+        ctx: Context<CancelRaffle>
+    ) -> Result<()> {
+        let event = ctx.accounts.cancel_raffle()?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // If the raffle failed, was cancelled, or the first place never claimed it,
+    // it will give the escrowed prize back to the maker
+    pub fn reclaim_prize(
+        ctx: Context<ReclaimPrize>
+    ) -> Result<()> {
+        let event = ctx.accounts.reclaim_prize()?;
+
+        emit_cpi!(event);
 
         Ok(())
     }
 
     // it will close the raffle and return the rent to the authority.
-    // pub close_raffle
+    // it will sweep the unclaimed prize once the claim period is over
+    pub fn close_raffle(
+        ctx: Context<CloseRaffle>
+    ) -> Result<()> {
+        let event = ctx.accounts.close_raffle()?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // The house authority takes out the fees collected in the treasury
+    // it will keep the treasury rent exempt
+    pub fn withdraw_treasury(
+        ctx: Context<WithdrawTreasury>,
+        amount: u64
+    ) -> Result<()> {
+        let event = ctx.accounts.withdraw_treasury(amount)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // The house authority changes the fee charged for each raffle and its cut of the tickets
+    // configs created with an older layout get reallocated
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        args: UpdateConfigArgs
+    ) -> Result<()> {
+        let event = ctx.accounts.update_config(args)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // The house authority proposes a new authority
+    // nothing changes until the new authority accepts it
+    pub fn propose_authority(
+        ctx: Context<ProposeAuthority>,
+        new_authority: Pubkey
+    ) -> Result<()> {
+        let event = ctx.accounts.propose_authority(new_authority)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // The proposed authority takes over the house
+    pub fn accept_authority(
+        ctx: Context<AcceptAuthority>
+    ) -> Result<()> {
+        let event = ctx.accounts.accept_authority()?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    // Raffles created with the vault seeded by the maker move their pot
+    // into the vault seeded by the raffle
+    pub fn migrate_vault(
+        ctx: Context<MigrateVault>
+    ) -> Result<()> {
+        let event = ctx.accounts.migrate_vault(&ctx.bumps)?;
+
+        emit_cpi!(event);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::BeeRafError, RaffleConfig};
+
+// Token accounts of a raffle paid with an SPL Token or Token-2022 mint
+pub struct TokenAccounts<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub vault_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+// It returns None for SOL raffles. For token raffles, every account must be
+// present and the vault token account must be the ATA of the vault PDA.
+pub fn token_accounts<'a, 'info>(
+    raffle_config: &RaffleConfig,
+    vault: &Pubkey,
+    mint: &'a Option<Box<InterfaceAccount<'info, Mint>>>,
+    vault_token_account: &'a Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+) -> Result<Option<TokenAccounts<'a, 'info>>> {
+    let Some(payment_mint) = raffle_config.payment_mint else {
+        return Ok(None);
+    };
+
+    let mint = mint.as_deref().ok_or(BeeRafError::MissingTokenAccounts)?;
+    let vault_token_account = vault_token_account.as_deref().ok_or(BeeRafError::MissingTokenAccounts)?;
+    let token_program = token_program.as_ref().ok_or(BeeRafError::MissingTokenAccounts)?;
+
+    require_keys_eq!(mint.key(), payment_mint, BeeRafError::InvalidPaymentMint);
+    require_keys_eq!(*mint.to_account_info().owner, token_program.key(), BeeRafError::InvalidTokenAccount);
+    require_keys_eq!(
+        vault_token_account.key(),
+        get_associated_token_address_with_program_id(vault, &payment_mint, &token_program.key()),
+        BeeRafError::InvalidTokenAccount
+    );
+
+    Ok(Some(TokenAccounts {
+        mint,
+        vault_token_account,
+        token_program,
+    }))
+}
+
+impl<'a, 'info> TokenAccounts<'a, 'info> {
+    // Token account of one of the parties of the raffle (buyer, maker or winner)
+    pub fn owned_by(
+        &self,
+        token_account: &'a Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+        owner: &Pubkey,
+    ) -> Result<&'a InterfaceAccount<'info, TokenAccount>> {
+        let token_account = token_account.as_deref().ok_or(BeeRafError::MissingTokenAccounts)?;
+
+        require_keys_eq!(token_account.mint, self.mint.key(), BeeRafError::InvalidTokenAccount);
+        require_keys_eq!(token_account.owner, *owner, BeeRafError::InvalidTokenAccount);
+
+        Ok(token_account)
+    }
+
+    // It returns the amount received by `to`, which is lower than `amount`
+    // when the mint has a Token-2022 transfer fee
+    pub fn transfer(
+        &self,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        authority: &AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let balance_before = balance(&to.to_account_info())?;
+
+        let cpi_accounts = TransferChecked {
+            from: from.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: authority.clone(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds
+        );
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        let balance_after = balance(&to.to_account_info())?;
+
+        balance_after
+            .checked_sub(balance_before)
+            .ok_or(BeeRafError::NumericalOverflow.into())
+    }
+}
+
+pub(crate) fn balance(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.try_borrow_data()?;
+
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
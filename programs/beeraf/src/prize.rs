@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+use mpl_core::instructions::TransferV1CpiBuilder;
+
+use crate::{error::BeeRafError, payment::balance, Prize};
+
+// Accounts needed to move an escrowed prize. Only the ones for the kind of prize are required.
+pub struct PrizeAccounts<'a, 'info> {
+    pub mpl_core_program: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub prize_asset: &'a Option<UncheckedAccount<'info>>,
+    pub prize_collection: &'a Option<UncheckedAccount<'info>>,
+    pub prize_mint: &'a Option<Box<InterfaceAccount<'info, Mint>>>,
+    pub source_token_account: &'a Option<UncheckedAccount<'info>>,
+    pub destination_token_account: &'a Option<UncheckedAccount<'info>>,
+    pub prize_token_program: &'a Option<Interface<'info, TokenInterface>>,
+    pub associated_token_program: &'a Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'a, 'info> PrizeAccounts<'a, 'info> {
+    // It moves the prize from `from` to `to` and returns the prize as received,
+    // which has a lower amount when the mint has a Token-2022 transfer fee.
+    // The destination token account is created when it does not exist.
+    pub fn transfer(
+        &self,
+        prize: &Prize,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<Prize> {
+        match *prize {
+            Prize::None => Ok(Prize::None),
+            Prize::Asset { asset } => {
+                let prize_asset = self.prize_asset.as_ref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+
+                require_keys_eq!(prize_asset.key(), asset, BeeRafError::InvalidPrize);
+
+                TransferV1CpiBuilder::new(self.mpl_core_program)
+                    .asset(&prize_asset.to_account_info())
+                    .collection(self.prize_collection.as_ref().map(|c| c.as_ref()))
+                    .payer(payer)
+                    .authority(Some(from))
+                    .new_owner(to)
+                    .system_program(Some(self.system_program))
+                    .invoke_signed(signer_seeds)?;
+
+                Ok(Prize::Asset { asset })
+            }
+            Prize::Token { mint, amount } => {
+                let prize_mint = self.prize_mint.as_deref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+                let source = self.source_token_account.as_ref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+                let destination = self.destination_token_account.as_ref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+                let token_program = self.prize_token_program.as_ref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+                let associated_token_program = self.associated_token_program.as_ref().ok_or(BeeRafError::MissingPrizeAccounts)?;
+
+                require_keys_eq!(prize_mint.key(), mint, BeeRafError::InvalidPrize);
+
+                // The associated token program makes sure the destination is the ATA of `to`
+                let cpi_accounts = Create {
+                    payer: payer.clone(),
+                    associated_token: destination.to_account_info(),
+                    authority: to.clone(),
+                    mint: prize_mint.to_account_info(),
+                    system_program: self.system_program.clone(),
+                    token_program: token_program.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new(associated_token_program.to_account_info(), cpi_accounts);
+
+                create_idempotent(cpi_ctx)?;
+
+                let balance_before = balance(&destination.to_account_info())?;
+
+                let cpi_accounts = TransferChecked {
+                    from: source.to_account_info(),
+                    mint: prize_mint.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: from.clone(),
+                };
+
+                let cpi_ctx = CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds
+                );
+
+                transfer_checked(cpi_ctx, amount, prize_mint.decimals)?;
+
+                let received = balance(&destination.to_account_info())?
+                    .checked_sub(balance_before)
+                    .ok_or(BeeRafError::NumericalOverflow)?;
+
+                Ok(Prize::Token { mint, amount: received })
+            }
+        }
+    }
+}
@@ -1,14 +1,137 @@
 use anchor_lang::prelude::*;
 
+use crate::error::BeeRafError;
+
 #[account]
 pub struct Config {
     pub authority: Pubkey,
     pub fee: u64,
     pub config_bump: u8,
     pub treasury_bump: u8,    
+    // Authority proposed by the current one, it takes over once it accepts
+    pub pending_authority: Option<Pubkey>,
+    // House cut of every ticket sale, in basis points
+    pub house_fee_bps: u16,
+    // Bounds every raffle of the house must respect
+    pub policy: RafflePolicy,
 }
 
 impl Config {
-    pub const INIT_SPACE:usize = 8 + 32 + 8  + 1 + 1;  
+    pub const INIT_SPACE:usize = 8 + 32 + 8  + 1 + 1 + (1 + 32) + 2 + RafflePolicy::INIT_SPACE;  
+}
+
+// Every bound is optional, None means no limit
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Default)]
+pub struct RafflePolicy {
+    pub max_raffle_fee_bps: Option<u16>,
+    pub min_ticket_price: Option<u64>,
+    pub max_ticket_price: Option<u64>,
+    pub min_slot_interval: Option<u64>,
+    pub max_slot_interval: Option<u64>,
+    // Share of the creation fee given back when a raffle is cancelled before selling any ticket
+    pub cancel_refund_bps: Option<u16>,
 }
 
+impl RafflePolicy {
+    pub const INIT_SPACE:usize = (1 + 2) + (1 + 8) * 4 + (1 + 2);
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_raffle_fee_bps.map_or(true, |max| max <= 10_000)
+                && self.cancel_refund_bps.map_or(true, |bps| bps <= 10_000),
+            BeeRafError::InvalidPolicy
+        );
+        require!(
+            bounds_ok(self.min_ticket_price, self.max_ticket_price)
+                && bounds_ok(self.min_slot_interval, self.max_slot_interval),
+            BeeRafError::InvalidPolicy
+        );
+
+        Ok(())
+    }
+
+    pub fn check_raffle(&self, raffle_fee: u64, ticket_price: u64, slot_interval: u64) -> Result<()> {
+        require!(raffle_fee <= 10_000, BeeRafError::RaffleFeeTooHigh);
+        require!(
+            self.max_raffle_fee_bps.map_or(true, |max| raffle_fee <= max as u64),
+            BeeRafError::RaffleFeeTooHigh
+        );
+
+        require!(ticket_price > 0, BeeRafError::TicketPriceTooLow);
+        require!(
+            self.min_ticket_price.map_or(true, |min| ticket_price >= min),
+            BeeRafError::TicketPriceTooLow
+        );
+        require!(
+            self.max_ticket_price.map_or(true, |max| ticket_price <= max),
+            BeeRafError::TicketPriceTooHigh
+        );
+
+        require!(
+            self.min_slot_interval.map_or(true, |min| slot_interval >= min),
+            BeeRafError::SlotIntervalTooShort
+        );
+        require!(
+            self.max_slot_interval.map_or(true, |max| slot_interval <= max),
+            BeeRafError::SlotIntervalTooLong
+        );
+
+        Ok(())
+    }
+}
+
+fn bounds_ok(min: Option<u64>, max: Option<u64>) -> bool {
+    match (min, max) {
+        (Some(min), Some(max)) => min <= max,
+        _ => true,
+    }
+}
+
+
+#[event]
+pub struct HouseInitialized {
+    pub house: Pubkey,
+    pub authority: Pubkey,
+    pub fee: u64,
+    pub house_fee_bps: u16,
+    pub policy: RafflePolicy,
+    pub slot: u64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub house: Pubkey,
+    pub authority: Pubkey,
+    // Values of the config once updated, fields left out of the update keep their value
+    pub fee: u64,
+    pub house_fee_bps: u16,
+    pub policy: RafflePolicy,
+    pub slot: u64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub house: Pubkey,
+    pub authority: Pubkey,
+    // It takes over once it accepts
+    pub pending_authority: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub house: Pubkey,
+    pub previous_authority: Pubkey,
+    pub authority: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub house: Pubkey,
+    pub amount: u64,
+    // Destination wallet, or its token account when withdrawing tokens of the mint
+    pub destination: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub slot: u64,
+}
@@ -3,3 +3,6 @@ pub use config::*;
 
 pub mod raffle_config;
 pub use raffle_config::*;
+
+pub mod ticket_data;
+pub use ticket_data::*;
@@ -1,18 +1,81 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    error::BeeRafError, CreateRaffleArgs, CLAIM_PERIOD, CLAIM_PERIOD_SECONDS, MAX_WINNERS, RANDOMNESS_TIMEOUT,
+    RANDOMNESS_TIMEOUT_SECONDS, SLOT_HASHES_WINDOW,
+};
+
 #[account]
 pub struct RaffleConfig {
     pub authority: Pubkey,
     pub collection: Pubkey,
+    // Last slot to buy tickets (0 when the raffle ends at a unix timestamp)
     pub slot: u64,
     pub ticket_price: u64,
     pub raffle_fee: u64,
     pub raffle_config_bump: u8,
     pub vault_bump: u8,
+    // Lamports (or tokens) in the vault that belong to this raffle
+    pub pot: u64,
+    // 0 for the legacy vault seeded by the maker, VAULT_VERSION for the per-raffle vault
+    pub vault_version: u8,
+    // Maximum number of tickets that can be sold
+    pub capacity: u32,
+    // Tickets to sell before the deadline, otherwise buyers get refunded (0 for no minimum)
+    pub min_tickets: u32,
+    // Maker fees held in the vault until the raffle gets resolved
+    pub escrowed_fees: u64,
+    // Slot and unix timestamp the winners were drawn, the raffle was cancelled or it started
+    // refunding at (0 until then). The claim period runs from them
+    pub resolved_slot: u64,
+    pub resolved_timestamp: i64,
+    // Hash of the secret seed the maker reveals when solving the raffle
+    pub commitment: [u8; 32],
+    // Slot hash captured after the deadline, mixed with the revealed seed
+    pub entropy: [u8; 32],
+    // Slot whose hash is captured, picked by the first capture_entropy call,
+    // or the slot the VRF randomness account was bound at (0 until then)
+    pub entropy_slot: u64,
+    pub randomness_source: RandomnessSource,
+    // VRF randomness account bound after the deadline
+    pub randomness_account: Pubkey,
+    // Pot at the moment the raffle was resolved, the prize tiers are shares of it
+    pub prize_pool: u64,
+    // Bit per place that has already been paid
+    pub claimed_places: u16,
+    // Basis points of the prize pool for each place (empty for a single winner)
+    pub prize_tiers: Vec<u16>,
+    // Winning ticket numbers, one per place
+    pub winners: Vec<u32>,
+    // SPL Token or Token-2022 mint the tickets are paid with (None for SOL)
+    pub payment_mint: Option<Pubkey>,
+    // Ticket numbers already refunded, refunds split what is left in the vault between the others
+    pub refunded_tickets: u32,
+    // Asset or tokens the maker escrowed in the vault for the first place
+    pub prize: Prize,
+    // The escrowed prize has left the vault, to the winner or back to the maker
+    pub prize_released: bool,
+    // Ticket numbers sold, a ticket asset can hold a range of them
+    pub tickets_sold: u32,
+    // House cut of every ticket sale in basis points, taken from the config when the raffle was created
+    pub house_fee_bps: u16,
+    // House cuts held in the vault until the raffle gets resolved
+    pub escrowed_house_fees: u64,
+    // Unix timestamp the raffle ends at (0 when it ends at a slot)
+    pub end_timestamp: i64,
+    // Unix timestamp before which tickets can not be bought (0 to start right away)
+    pub start_timestamp: i64,
+    // The raffle can be solved as soon as every ticket is sold, without waiting for the deadline
+    pub end_on_sell_out: bool,
+    // Config fee paid by the maker when the raffle was created
+    pub creation_fee: u64,
+    pub status: RaffleStatus,
+    // Layout version, see RAFFLE_CONFIG_VERSION
+    pub version: u8,
 }
 
 impl RaffleConfig {
-    pub const INIT_SPACE:usize = 8 + 32 + 32 + 8  + 8 + 4 + 8 +  8 + 1 + 1;  
+    pub const INIT_SPACE:usize = 8 + 32 + 32 + 8  + 8 + 4 + 8 +  8 + 1 + 1 + 8 + 1 + 4 + 4 + 8 + 8 + 8 + 32 + 32 + 8 + 1 + 32 + 8 + 2 + (4 + 2 * MAX_WINNERS) + (4 + 4 * MAX_WINNERS) + (1 + 32) + 4 + (1 + 32 + 8) + 1 + 4 + 2 + 8 + 8 + 8 + 1 + 8 + 1 + 1;  
 
     pub fn to_slice(&self) -> Vec<u8> {
         let mut info = self.authority.to_bytes().to_vec();
@@ -25,22 +88,364 @@ impl RaffleConfig {
         
         info
     }
+
+    // Raffles end after their slot, or after their unix timestamp when it is set
+    pub fn is_over(&self, clock: &Clock) -> bool {
+        match self.end_timestamp {
+            0 => clock.slot > self.slot,
+            end_timestamp => clock.unix_timestamp > end_timestamp,
+        }
+    }
+
+    // Ticket sales are over, either at the deadline or when the raffle sells out and ends on it
+    pub fn sales_ended(&self, clock: &Clock, num_minted: u32) -> bool {
+        self.is_over(clock)
+            || (self.end_on_sell_out && self.sold(num_minted) >= self.capacity)
+    }
+
+    // Ticket sales are still running, or over but the winners are not drawn yet
+    pub fn is_unresolved(&self) -> bool {
+        matches!(
+            self.status,
+            RaffleStatus::Open | RaffleStatus::SoldOut | RaffleStatus::AwaitingRandomness
+        )
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        matches!(self.status, RaffleStatus::Resolved | RaffleStatus::Claimed)
+    }
+
+    // The raffle ended below its minimum of tickets, or its randomness was never requested or revealed.
+    // It is marked Refunding on the first refund
+    pub fn has_failed(&self, clock: &Clock, num_minted: u32) -> bool {
+        self.status == RaffleStatus::Refunding
+            || (self.is_unresolved()
+                && self.is_over(clock)
+                && self.sold(num_minted) < self.min_tickets.max(1))
+            || self.randomness_not_requested(clock)
+            || self.randomness_timed_out(clock)
+    }
+
+    // Only the maker binds the VRF request, so a maker who never does can not hold the pot forever
+    pub fn randomness_not_requested(&self, clock: &Clock) -> bool {
+        self.randomness_source == RandomnessSource::Vrf
+            && matches!(self.status, RaffleStatus::Open | RaffleStatus::SoldOut)
+            && match self.end_timestamp {
+                0 => clock.slot > self.slot.saturating_add(RANDOMNESS_TIMEOUT),
+                end_timestamp => clock.unix_timestamp > end_timestamp.saturating_add(RANDOMNESS_TIMEOUT_SECONDS),
+            }
+    }
+
+    // The maker never revealed the seed, or the oracle never fulfilled the VRF request
+    pub fn randomness_timed_out(&self, clock: &Clock) -> bool {
+        self.status == RaffleStatus::AwaitingRandomness
+            && self.entropy_slot > 0
+            && clock.slot > self.entropy_slot.saturating_add(RANDOMNESS_TIMEOUT)
+    }
+
+    // Nobody captured the entropy while the hash of its slot was in the SlotHashes window,
+    // capture_entropy schedules a new slot
+    pub fn entropy_expired(&self, clock: &Clock) -> bool {
+        self.randomness_source == RandomnessSource::CommitReveal
+            && matches!(self.status, RaffleStatus::Open | RaffleStatus::SoldOut)
+            && self.entropy_slot > 0
+            && clock.slot > self.entropy_slot.saturating_add(SLOT_HASHES_WINDOW)
+    }
+
+    pub fn has_started(&self, clock: &Clock) -> bool {
+        clock.unix_timestamp >= self.start_timestamp
+    }
+
+    pub fn set_resolved(&mut self, clock: &Clock) {
+        self.resolved_slot = clock.slot;
+        self.resolved_timestamp = clock.unix_timestamp;
+    }
+
+    // The winners (or the buyers to refund) had their time to claim, so what is left can be swept.
+    // A failed raffle nobody refunded yet counts it from its end, when it stopped selling
+    pub fn claim_period_over(&self, clock: &Clock) -> Result<bool> {
+        let (slot, timestamp) = match self.resolved_slot {
+            0 => (self.slot, self.end_timestamp),
+            resolved_slot => (resolved_slot, self.resolved_timestamp),
+        };
+
+        let over = match self.end_timestamp {
+            0 => clock.slot > slot
+                .checked_add(CLAIM_PERIOD)
+                .ok_or(BeeRafError::NumericalOverflow)?,
+            _ => clock.unix_timestamp > timestamp
+                .checked_add(CLAIM_PERIOD_SECONDS)
+                .ok_or(BeeRafError::NumericalOverflow)?,
+        };
+
+        Ok(over)
+    }
+
+    // Raffles that sold before ticket ranges existed had one number per minted asset
+    pub fn sold(&self, num_minted: u32) -> u32 {
+        match self.tickets_sold {
+            0 => num_minted,
+            tickets_sold => tickets_sold,
+        }
+    }
+
+    // Basis points share of an amount, rounded down
+    pub fn bps_share(amount: u64, bps: u64) -> Result<u64> {
+        let share = (amount as u128)
+            .checked_mul(bps as u128)
+            .ok_or(BeeRafError::NumericalOverflow)?
+            / 10_000;
+
+        u64::try_from(share).map_err(|_| BeeRafError::NumericalOverflow.into())
+    }
+
+    // Raffles without prize tiers have a single winner taking the whole pool
+    pub fn tiers(&self) -> Vec<u16> {
+        match self.prize_tiers.is_empty() {
+            true => vec![10_000],
+            false => self.prize_tiers.clone(),
+        }
+    }
+
+    // Share of the prize pool for a place. When fewer tickets than places were sold,
+    // the shares of the places with a winner are scaled up to the whole pool
+    pub fn prize_share(&self, place: usize) -> Result<u64> {
+        let tiers = self.tiers();
+
+        let assigned_bps: u128 = tiers
+            .iter()
+            .take(self.winners.len())
+            .map(|bps| *bps as u128)
+            .sum();
+
+        let share = (self.prize_pool as u128)
+            .checked_mul(tiers[place] as u128)
+            .ok_or(BeeRafError::NumericalOverflow)?
+            .checked_div(assigned_bps)
+            .ok_or(BeeRafError::NumericalOverflow)?;
+
+        u64::try_from(share).map_err(|_| BeeRafError::NumericalOverflow.into())
+    }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomnessSource {
+    // The maker reveals a committed seed mixed with a slot hash
+    #[default]
+    CommitReveal,
+    // A VRF oracle reveals the randomness (requires the `vrf` feature)
+    Vrf,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum RaffleStatus {
+    // Tickets are on sale, or the deadline passed and nobody moved the raffle forward yet
+    #[default]
+    Open,
+    // Every ticket has been sold
+    SoldOut,
+    // Ticket sales are over and the entropy has been captured (or the VRF randomness account bound)
+    AwaitingRandomness,
+    // The winners are drawn and can claim their prizes
+    Resolved,
+    // Every place has been paid
+    Claimed,
+    // The maker cancelled the raffle after tickets were sold, holders get refunded
+    Cancelled,
+    // The raffle ended below its minimum of tickets, holders get refunded
+    Refunding,
+    // The raffle has been closed. It is never stored, as the account goes away with the raffle:
+    // indexers set it from RaffleClosed, which carries the status the raffle was closed from
+    Closed,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, PartialEq, Eq)]
+pub enum RaffleEnd {
+    // Slots from the creation until the raffle ends
+    Slot { slot_interval: u64 },
+    // Unix timestamp the raffle ends at
+    UnixTimestamp { end_timestamp: i64 },
 }
 
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Prize {
+    // The winners only share the pot
+    #[default]
+    None,
+    // An mpl-core asset owned by the vault
+    Asset { asset: Pubkey },
+    // An amount of SPL Token or Token-2022 tokens, a legacy SPL NFT is an amount of 1
+    Token { mint: Pubkey, amount: u64 },
+}
+
+// Events are emitted through a self-CPI (emit_cpi!), so they can not be lost to log truncation.
+// They carry the keys and amounts an indexer needs to rebuild the raffle history
+// without fetching any account, amounts are in lamports or tokens of the payment mint
+
 #[event]
-pub struct BuyEvent {
+pub struct RaffleCreated {
+    pub house: Pubkey,
+    pub maker: Pubkey,
+    pub raffle: Pubkey,
+    pub raffle_config: Pubkey,
+    pub args: CreateRaffleArgs,
+    // Last slot to buy tickets (0 when the raffle ends at a unix timestamp)
+    pub end_slot: u64,
+    // Config fee the maker paid to the treasury
+    pub creation_fee: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TicketPurchased {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    // Ticket asset holding the numbers from first_ticket to last_ticket
+    pub ticket: Pubkey,
+    pub first_ticket: u32,
+    pub last_ticket: u32,
+    pub payment_mint: Option<Pubkey>,
+    // Total paid for the ticket, split between the maker, the vault and the house
+    pub price: u64,
     pub maker_fee: u64,
     pub vault_earning: u64,
+    pub house_fee: u64,
+    pub slot: u64,
 }
 
+#[event]
+pub struct RaffleResolved {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub randomness_source: RandomnessSource,
+    // Seed revealed by the maker, or the hash of the VRF randomness
+    pub seed: [u8; 32],
+    // Slot hash captured after the deadline (zeroes for VRF raffles)
+    pub entropy: [u8; 32],
+    // Hash the winning numbers are drawn from
+    pub roll: [u8; 32],
+    pub winners: Vec<u32>,
+    pub tickets_sold: u32,
+    pub prize_pool: u64,
+    pub slot: u64,
+}
 
 #[event]
-pub struct RafEvent {
+pub struct PrizeClaimed {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub ticket: Pubkey,
+    // First winning number of the raffle
     pub winner: u32,
+    pub first_ticket: u32,
+    pub last_ticket: u32,
+    pub payment_mint: Option<Pubkey>,
+    // Share of the pot paid to the buyer, 0 when the ticket did not win
+    pub claimed_amount: u64,
+    // The buyer also took the escrowed prize of the first place
+    pub prize_released: bool,
+    pub slot: u64,
 }
 
 #[event]
-pub struct WinnerEvent {
-    pub winner: u32,
-    pub ticket: u32,
+pub struct RaffleClosed {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub maker: Pubkey,
+    // Status the raffle was closed from
+    pub status: RaffleStatus,
+    // Unclaimed prizes of a resolved raffle, or maker fees of a failed one, swept back to the maker
+    pub swept: u64,
+    // Unrefunded tickets and house cut of a failed or cancelled raffle, swept to the treasury
+    pub swept_to_treasury: u64,
+    // The collection had no ticket left and was burned
+    pub collection_burned: bool,
+    pub slot: u64,
+}
+
+#[event]
+pub struct EntropyCaptured {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub entropy_slot: u64,
+    // Hash of the entropy slot, zeroes when the call only scheduled it
+    pub entropy: [u8; 32],
+    // AwaitingRandomness once the hash is captured
+    pub status: RaffleStatus,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RandomnessRequested {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    // VRF randomness account bound to the raffle
+    pub randomness: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RaffleCancelled {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub maker: Pubkey,
+    pub tickets_sold: u32,
+    // Part of the creation fee the treasury gave back, only when no ticket was sold
+    pub creation_fee_refund: u64,
+    // Nothing was sold, so the raffle config was closed along with the collection
+    pub closed: bool,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TicketRefunded {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    // Burned ticket asset holding the numbers from first_ticket to last_ticket
+    pub ticket: Pubkey,
+    pub first_ticket: u32,
+    pub last_ticket: u32,
+    pub payment_mint: Option<Pubkey>,
+    // Share of the pot, maker fees and house cuts paid back to the buyer
+    pub refund: u64,
+    // Part of the refund that was the escrowed house cut
+    pub house_fee: u64,
+    // Refunding when the raffle failed, Cancelled when the maker cancelled it
+    pub status: RaffleStatus,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizeReclaimed {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub maker: Pubkey,
+    pub prize: Prize,
+    // Refunding when the raffle failed, the status is kept otherwise
+    pub status: RaffleStatus,
+    pub slot: u64,
+}
+
+#[event]
+pub struct SeedCommitted {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    // Hash of the seed the maker reveals when solving the raffle
+    pub commitment: [u8; 32],
+    pub slot: u64,
+}
+
+#[event]
+pub struct RaffleMigrated {
+    pub house: Pubkey,
+    pub raffle: Pubkey,
+    pub maker: Pubkey,
+    // Moved from the maker vault into the vault of the raffle
+    pub pot: u64,
+    // Claimed for a legacy raffle that already had a winner
+    pub status: RaffleStatus,
+    pub slot: u64,
 }
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use mpl_core::{
+    accounts::BaseAssetV1, fetch_external_plugin_adapter_data_info, fetch_plugin, instructions::WriteExternalPluginAdapterDataV1CpiBuilder, types::{Attributes, ExternalPluginAdapterKey, PluginAuthority, PluginType}
+};
+
+use crate::{error::BeeRafError, TICKET_DATA_VERSION};
+
+// Binary layout stored in the AppData plugin of every ticket,
+// so wallets and indexers can read the ticket status from the asset
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct TicketData {
+    pub version: u8,
+    pub purchase_slot: u64,
+    pub price_paid: u64,
+    pub scratched: bool,
+    pub claimed_amount: u64,
+    pub refunded: bool,
+}
+
+impl TicketData {
+    pub fn new(purchase_slot: u64, price_paid: u64) -> Self {
+        Self {
+            version: TICKET_DATA_VERSION,
+            purchase_slot,
+            price_paid,
+            ..Default::default()
+        }
+    }
+
+    // The AppData plugin is keyed by its data authority, which is the raffle config
+    fn plugin_key(raffle_config: Pubkey) -> ExternalPluginAdapterKey {
+        ExternalPluginAdapterKey::AppData(PluginAuthority::Address {
+            address: raffle_config,
+        })
+    }
+
+    pub fn read(ticket: &AccountInfo, raffle_config: Pubkey) -> Result<Self> {
+        let (offset, len) = fetch_external_plugin_adapter_data_info::<BaseAssetV1>(
+            ticket,
+            None,
+            &Self::plugin_key(raffle_config),
+        )
+        .map_err(|_| BeeRafError::InvalidTicketData)?;
+
+        // Tickets bought before the ticket data existed have nothing written yet
+        if len == 0 {
+            return Ok(Self::new(0, 0));
+        }
+
+        let data = ticket.try_borrow_data()?;
+        let ticket_data = Self::try_from_slice(&data[offset..offset + len])
+            .map_err(|_| BeeRafError::InvalidTicketData)?;
+
+        require!(ticket_data.version == TICKET_DATA_VERSION, BeeRafError::InvalidTicketData);
+
+        Ok(ticket_data)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn write<'info>(
+        &self,
+        mpl_core_program: &AccountInfo<'info>,
+        ticket: &AccountInfo<'info>,
+        raffle: &AccountInfo<'info>,
+        raffle_config: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        WriteExternalPluginAdapterDataV1CpiBuilder::new(mpl_core_program)
+            .asset(ticket)
+            .collection(Some(raffle))
+            .payer(payer)
+            .authority(Some(raffle_config))
+            .system_program(system_program)
+            .key(Self::plugin_key(raffle_config.key()))
+            .data(self.try_to_vec()?)
+            .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+}
+
+// First and last ticket numbers held by a ticket asset.
+// Tickets bought before ranges existed only have the Ticket Number attribute.
+pub fn ticket_range(ticket: &AccountInfo) -> Result<(u32, u32)> {
+    let (_, ticket_attribute_list, _) = fetch_plugin::<BaseAssetV1, Attributes>(
+        ticket,
+        PluginType::Attributes,
+    )?;
+
+    let attribute = |key: &str| -> Result<Option<u32>> {
+        ticket_attribute_list
+            .attribute_list
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.parse::<u32>().map_err(|_| BeeRafError::NumericalOverflow.into()))
+            .transpose()
+    };
+
+    let first = attribute("Ticket Number")?.ok_or(BeeRafError::MissingWinnerAttribute)?;
+    let last = attribute("Last Ticket Number")?.unwrap_or(first);
+
+    Ok((first, last))
+}
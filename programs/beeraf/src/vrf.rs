@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::error::BeeRafError;
+
+// ORAO-style randomness account: discriminator (8 bytes), seed (32 bytes) and randomness (64 bytes)
+pub const RANDOMNESS_OFFSET: usize = 8 + 32;
+
+#[cfg(not(feature = "mock-vrf"))]
+pub const VRF_PROGRAM_ID: Pubkey = solana_program::pubkey!("VRFzZoJdhFWL8rkvu87LpKM3RbcVezpMEc6X5GVDr7y");
+
+// Local mock randomness program, so the tests can run offline
+#[cfg(feature = "mock-vrf")]
+pub const VRF_PROGRAM_ID: Pubkey = solana_program::pubkey!("4fnhqN7DczoSSPb9CXUG9ejC47xkMwVbCKcuwoLLVXfL");
+
+// It returns None while the oracle has not fulfilled the randomness
+pub fn read_randomness(account: &AccountInfo) -> Result<Option<[u8; 64]>> {
+    require_keys_eq!(*account.owner, VRF_PROGRAM_ID, BeeRafError::InvalidRandomnessAccount);
+
+    let data = account.try_borrow_data()?;
+
+    require!(data.len() >= RANDOMNESS_OFFSET + 64, BeeRafError::InvalidRandomnessAccount);
+
+    let mut randomness = [0u8; 64];
+    randomness.copy_from_slice(&data[RANDOMNESS_OFFSET..RANDOMNESS_OFFSET + 64]);
+
+    if randomness == [0u8; 64] {
+        return Ok(None);
+    }
+
+    Ok(Some(randomness))
+}
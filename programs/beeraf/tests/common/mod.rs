@@ -0,0 +1,701 @@
+// Harness shared by the integration tests. The beeraf and mpl-core programs run
+// in an in-process bank, so no validator, airdrop or genesis setup is needed.
+// Instructions without accounts to tamper with are built by beeraf-client.
+//
+// Both programs are loaded from target/deploy, run `anchor build` and
+// `./dump_program.sh` from the workspace root before `cargo test`.
+
+#![allow(dead_code)]
+
+use anchor_lang::{
+    error::ERROR_CODE_OFFSET,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use beeraf::{
+    accounts, error::BeeRafError, instruction, BuyTicketArgs, CreateRaffleArgs, Prize, RaffleConfig,
+    RaffleEnd, RafflePolicy, RandomnessSource,
+};
+use beeraf_client::{
+    cancel_raffle, capture_entropy, close_raffle, commit_seed, ed25519_instruction, find_config,
+    find_event_authority, find_legacy_vault, find_raffle_config, find_treasury, find_vault, initialize,
+    migrate_vault, refund_ticket, scratch_ticket, RaffleKeys, RaffleOptions,
+};
+use mpl_core::types::{Attribute, Attributes, Plugin, PluginAuthority, PluginAuthorityPair};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    compute_budget::ComputeBudgetInstruction,
+    hash::hash,
+    instruction::InstructionError,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const FEE: u64 = LAMPORTS_PER_SOL / 10;
+pub const HOUSE_FEE_BPS: u16 = 200;
+pub const TICKET_PRICE: u64 = LAMPORTS_PER_SOL / 100;
+pub const RAFFLE_FEE: u64 = 500;
+pub const SLOT_INTERVAL: u64 = 100;
+pub const CAPACITY: u32 = 10;
+pub const SEED: [u8; 32] = [7; 32];
+// Space the first version of the program allocated for a RaffleConfig
+pub const LEGACY_RAFFLE_CONFIG_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 4 + 8 + 8 + 1 + 1;
+
+pub fn treasury(house: &Pubkey) -> Pubkey {
+    find_treasury(house).0
+}
+
+pub fn config(house: &Pubkey) -> Pubkey {
+    find_config(house).0
+}
+
+pub fn raffle_config(house: &Pubkey, raffle: &Pubkey) -> Pubkey {
+    find_raffle_config(house, raffle).0
+}
+
+pub fn vault(raffle: &Pubkey) -> Pubkey {
+    find_vault(raffle).0
+}
+
+pub fn legacy_vault(maker: &Pubkey) -> Pubkey {
+    find_legacy_vault(maker).0
+}
+
+pub fn event_authority() -> Pubkey {
+    find_event_authority().0
+}
+
+pub fn default_policy() -> RafflePolicy {
+    RafflePolicy {
+        max_raffle_fee_bps: Some(1_000),
+        cancel_refund_bps: Some(5_000),
+        ..Default::default()
+    }
+}
+
+// House cut of each ticket, held in the vault until the raffle is resolved
+pub fn house_fee() -> u64 {
+    TICKET_PRICE * HOUSE_FEE_BPS as u64 / 10_000
+}
+
+// What the vault gets from each ticket once the maker and house fees are taken
+pub fn vault_earning() -> u64 {
+    TICKET_PRICE - TICKET_PRICE * RAFFLE_FEE / 10_000 - house_fee()
+}
+
+// RaffleConfig as the first version of the program stored it, before the pot, the status
+// and the layout version. Its tickets were paid into the vault seeded by the maker
+pub fn legacy_raffle_config(house: &Pubkey, maker: &Pubkey, raffle: &Pubkey) -> (Pubkey, Account) {
+    let (address, raffle_config_bump) = find_raffle_config(house, raffle);
+    let (_, vault_bump) = find_legacy_vault(maker);
+
+    let mut data = RaffleConfig::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(maker.as_ref());
+    data.extend_from_slice(raffle.as_ref());
+    data.extend_from_slice(&SLOT_INTERVAL.to_le_bytes());
+    data.extend_from_slice(&TICKET_PRICE.to_le_bytes());
+    data.extend_from_slice(&RAFFLE_FEE.to_le_bytes());
+    data.extend_from_slice(&[raffle_config_bump, vault_bump]);
+    data.resize(LEGACY_RAFFLE_CONFIG_SPACE, 0);
+
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: beeraf::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    (address, account)
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: beeraf::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// It fails the test unless the transaction failed with the given program error
+pub fn assert_error<T: std::fmt::Debug>(result: Result<T, BanksClientError>, error: BeeRafError) {
+    let message = error.to_string();
+    let code = ERROR_CODE_OFFSET + error as u32;
+
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+            assert_eq!(actual, code, "expected error \"{}\"", message)
+        }
+        other => panic!("expected error \"{}\", got {:?}", message, other),
+    }
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub house: Keypair,
+    pub maker: Keypair,
+    sent: u32,
+}
+
+impl TestEnv {
+    pub async fn start() -> Self {
+        Self::start_with_accounts(Keypair::new(), Keypair::new(), vec![]).await
+    }
+
+    // The bank starts with the given accounts, such as raffles stored by older versions of the program
+    pub async fn start_with_accounts(house: Keypair, maker: Keypair, accounts: Vec<(Pubkey, Account)>) -> Self {
+        // cargo test-sbf sets the directory of the built programs, cargo test does not
+        if std::env::var("SBF_OUT_DIR").is_err() && std::env::var("BPF_OUT_DIR").is_err() {
+            std::env::set_var(
+                "SBF_OUT_DIR",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy"),
+            );
+        }
+
+        let mut program_test = ProgramTest::new("beeraf", beeraf::ID, None);
+        program_test.add_program("mpl_core", mpl_core::ID, None);
+        #[cfg(feature = "mock-vrf")]
+        program_test.add_program("mock_vrf", mock_vrf::ID, None);
+        program_test.prefer_bpf(true);
+
+        for wallet in [house.pubkey(), maker.pubkey()] {
+            program_test.add_account(
+                wallet,
+                Account::new(100 * LAMPORTS_PER_SOL, 0, &system_program::ID),
+            );
+        }
+
+        for (address, account) in accounts {
+            program_test.add_account(address, account);
+        }
+
+        let context = program_test.start_with_context().await;
+
+        Self { context, house, maker, sent: 0 }
+    }
+
+    // A house initialized with the default fees and policy
+    pub async fn start_with_house() -> Self {
+        let mut env = Self::start().await;
+
+        env.initialize(FEE, HOUSE_FEE_BPS, default_policy()).await.unwrap();
+
+        env
+    }
+
+    // A house with a raffle created by the first version of the program, whose
+    // collection only holds the Capacity attribute when one is given
+    pub async fn start_with_legacy_raffle(capacity: Option<u32>) -> (Self, Pubkey) {
+        Self::start_with_legacy_sales(capacity, 0, 0).await
+    }
+
+    // The legacy raffle already sold the given tickets, and the maker vault holds the given balance
+    pub async fn start_with_legacy_sales(capacity: Option<u32>, tickets: u32, vault_balance: u64) -> (Self, Pubkey) {
+        let house = Keypair::new();
+        let maker = Keypair::new();
+        let raffle = Keypair::new();
+
+        let mut accounts = vec![legacy_raffle_config(&house.pubkey(), &maker.pubkey(), &raffle.pubkey())];
+
+        if vault_balance > 0 {
+            accounts.push((
+                legacy_vault(&maker.pubkey()),
+                Account::new(vault_balance, 0, &system_program::ID),
+            ));
+        }
+
+        let mut env = Self::start_with_accounts(house, maker, accounts).await;
+
+        env.initialize(FEE, HOUSE_FEE_BPS, default_policy()).await.unwrap();
+
+        let attribute_list = capacity
+            .into_iter()
+            .map(|capacity| Attribute {
+                key: "Capacity".to_string(),
+                value: capacity.to_string(),
+            })
+            .collect();
+
+        // The maker mints the tickets, then hands the collection over to the raffle config
+        let maker = env.maker.insecure_clone();
+
+        let create_collection = mpl_core::instructions::CreateCollectionV2Builder::new()
+            .collection(raffle.pubkey())
+            .update_authority(Some(maker.pubkey()))
+            .payer(env.context.payer.pubkey())
+            .name("Raffle".to_string())
+            .uri("https://example.com/raffle.json".to_string())
+            .plugins(vec![PluginAuthorityPair {
+                plugin: Plugin::Attributes(Attributes { attribute_list }),
+                authority: Some(PluginAuthority::UpdateAuthority),
+            }])
+            .instruction();
+
+        env.send(&[create_collection], &[&raffle]).await.unwrap();
+
+        for _ in 0..tickets {
+            let ticket = Keypair::new();
+
+            let create_ticket = mpl_core::instructions::CreateV2Builder::new()
+                .asset(ticket.pubkey())
+                .collection(Some(raffle.pubkey()))
+                .authority(Some(maker.pubkey()))
+                .payer(env.context.payer.pubkey())
+                .owner(Some(maker.pubkey()))
+                .name("Ticket".to_string())
+                .uri("https://example.com/ticket.json".to_string())
+                .instruction();
+
+            env.send(&[create_ticket], &[&maker, &ticket]).await.unwrap();
+        }
+
+        let update_collection = mpl_core::instructions::UpdateCollectionV1Builder::new()
+            .collection(raffle.pubkey())
+            .payer(env.context.payer.pubkey())
+            .authority(Some(maker.pubkey()))
+            .new_update_authority(Some(raffle_config(&env.house.pubkey(), &raffle.pubkey())))
+            .instruction();
+
+        env.send(&[update_collection], &[&maker]).await.unwrap();
+
+        (env, raffle.pubkey())
+    }
+
+    pub async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        // Identical transactions would be rejected as already processed,
+        // so every one gets its own compute unit limit
+        self.sent += 1;
+
+        let mut instructions = instructions.to_vec();
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(1_000_000 + self.sent));
+
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    pub async fn funded_keypair(&mut self) -> Keypair {
+        let keypair = Keypair::new();
+
+        let transfer = system_instruction::transfer(
+            &self.context.payer.pubkey(),
+            &keypair.pubkey(),
+            10 * LAMPORTS_PER_SOL,
+        );
+
+        self.send(&[transfer], &[]).await.unwrap();
+
+        keypair
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    pub async fn warp_slots(&mut self, slots: u64) {
+        let slot = self.clock().await.slot;
+
+        self.context.warp_to_slot(slot + slots).unwrap();
+    }
+
+    pub async fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.context.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> Option<T> {
+        let account = self.context.banks_client.get_account(*address).await.unwrap()?;
+
+        Some(T::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn keys(&self, raffle: &Pubkey) -> RaffleKeys {
+        RaffleKeys {
+            house: self.house.pubkey(),
+            maker: self.maker.pubkey(),
+            raffle: *raffle,
+        }
+    }
+
+    pub async fn raffle_config(&mut self, raffle: &Pubkey) -> RaffleConfig {
+        let address = raffle_config(&self.house.pubkey(), raffle);
+
+        self.account(&address).await.expect("raffle config should exist")
+    }
+
+    pub async fn initialize(&mut self, fee: u64, house_fee_bps: u16, policy: RafflePolicy) -> Result<(), BanksClientError> {
+        let house = self.house.insecure_clone();
+
+        let initialize = initialize(&house.pubkey(), fee, house_fee_bps, policy);
+
+        self.send(&[initialize], &[&house]).await
+    }
+
+    pub fn raffle_args(&self) -> CreateRaffleArgs {
+        CreateRaffleArgs {
+            name: "Raffle".to_string(),
+            uri: "https://example.com/raffle.json".to_string(),
+            ticket_price: TICKET_PRICE,
+            raffle_fee: RAFFLE_FEE,
+            end: RaffleEnd::Slot { slot_interval: SLOT_INTERVAL },
+            start_timestamp: None,
+            end_on_sell_out: false,
+            capacity: CAPACITY,
+            min_tickets: None,
+            commitment: hash(&SEED).to_bytes(),
+            randomness_source: RandomnessSource::CommitReveal,
+            prize_tiers: vec![],
+            payment_mint: None,
+            prize: Prize::None,
+        }
+    }
+
+    pub fn create_raffle_accounts(&self, raffle: &Pubkey) -> accounts::CreateRaffle {
+        let house = self.house.pubkey();
+
+        accounts::CreateRaffle {
+            maker: self.maker.pubkey(),
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            raffle: *raffle,
+            raffle_config: raffle_config(&house, raffle),
+            vault: vault(raffle),
+            payment_mint: None,
+            vault_token_account: None,
+            treasury_token_account: None,
+            prize_asset: None,
+            prize_collection: None,
+            prize_mint: None,
+            maker_prize_token_account: None,
+            vault_prize_token_account: None,
+            prize_token_program: None,
+            mpl_core_program: mpl_core::ID,
+            token_program: None,
+            associated_token_program: None,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        }
+    }
+
+    pub async fn create_raffle_with(
+        &mut self,
+        accounts: accounts::CreateRaffle,
+        args: CreateRaffleArgs,
+        raffle: &Keypair,
+    ) -> Result<Pubkey, BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let create_raffle = ix(accounts, instruction::CreateRaffle { args });
+
+        self.send(&[create_raffle], &[&maker, raffle]).await?;
+
+        Ok(raffle.pubkey())
+    }
+
+    pub async fn create_raffle(&mut self, args: CreateRaffleArgs) -> Result<Pubkey, BanksClientError> {
+        let raffle = Keypair::new();
+        let accounts = self.create_raffle_accounts(&raffle.pubkey());
+
+        self.create_raffle_with(accounts, args, &raffle).await
+    }
+
+    pub fn buy_ticket_accounts(&self, raffle: &Pubkey, buyer: &Pubkey, ticket: &Pubkey) -> accounts::BuyTicket {
+        let house = self.house.pubkey();
+
+        accounts::BuyTicket {
+            buyer: *buyer,
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            raffle: *raffle,
+            raffle_config: raffle_config(&house, raffle),
+            ticket: *ticket,
+            vault: vault(raffle),
+            payment_mint: None,
+            buyer_token_account: None,
+            vault_token_account: None,
+            mpl_core_program: mpl_core::ID,
+            token_program: None,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        }
+    }
+
+    pub async fn buy_ticket(&mut self, raffle: &Pubkey, buyer: &Keypair, quantity: u32) -> Result<Pubkey, BanksClientError> {
+        let ticket = Keypair::new();
+
+        let buy_ticket = ix(
+            self.buy_ticket_accounts(raffle, &buyer.pubkey(), &ticket.pubkey()),
+            instruction::BuyTicket {
+                args: BuyTicketArgs {
+                    name: "Ticket".to_string(),
+                    uri: "https://example.com/ticket.json".to_string(),
+                    quantity,
+                },
+            },
+        );
+
+        self.send(&[buy_ticket], &[buyer, &ticket]).await?;
+
+        Ok(ticket.pubkey())
+    }
+
+    pub async fn commit_seed(&mut self, raffle: &Pubkey, commitment: [u8; 32]) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let commit_seed = commit_seed(&self.keys(raffle), commitment);
+
+        self.send(&[commit_seed], &[&maker]).await
+    }
+
+    pub async fn migrate_vault(&mut self, raffle: &Pubkey, maker: &Keypair) -> Result<(), BanksClientError> {
+        let keys = RaffleKeys {
+            maker: maker.pubkey(),
+            ..self.keys(raffle)
+        };
+
+        let migrate_vault = migrate_vault(&keys);
+
+        self.send(&[migrate_vault], &[maker]).await
+    }
+
+    // Schedules the entropy slot, then captures its hash once the slot is in SlotHashes
+    pub async fn capture_entropy(&mut self, raffle: &Pubkey) -> Result<(), BanksClientError> {
+        self.send_capture_entropy(raffle).await?;
+
+        let entropy_slot = self.raffle_config(raffle).await.entropy_slot;
+
+        self.warp_to_entropy_slot(entropy_slot).await;
+        self.send_capture_entropy(raffle).await
+    }
+
+    // A slot hash is only in SlotHashes once the next slot is produced
+    pub async fn warp_to_entropy_slot(&mut self, entropy_slot: u64) {
+        self.context.warp_to_slot(entropy_slot).unwrap();
+        self.context.warp_to_slot(entropy_slot + 1).unwrap();
+    }
+
+    pub async fn send_capture_entropy(&mut self, raffle: &Pubkey) -> Result<(), BanksClientError> {
+        let capture_entropy = capture_entropy(&self.keys(raffle), &self.context.payer.pubkey());
+
+        self.send(&[capture_entropy], &[]).await
+    }
+
+    pub fn solve_raffle_accounts(&self, raffle: &Pubkey) -> accounts::SolveRaffle {
+        let house = self.house.pubkey();
+
+        accounts::SolveRaffle {
+            maker: self.maker.pubkey(),
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            raffle: *raffle,
+            raffle_config: raffle_config(&house, raffle),
+            vault: vault(raffle),
+            mpl_core_program: mpl_core::ID,
+            instruction_sysvar: sysvar::instructions::ID,
+            randomness: None,
+            payment_mint: None,
+            maker_token_account: None,
+            vault_token_account: None,
+            treasury_token_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        }
+    }
+
+    // The Ed25519 instruction must come first, signed by `signer` over the raffle details
+    pub async fn solve_raffle_instructions(&mut self, raffle: &Pubkey, signer: &Keypair, seed: [u8; 32]) -> Vec<Instruction> {
+        let message = self.raffle_config(raffle).await.to_slice();
+        let signature = signer.sign_message(&message);
+
+        vec![
+            ed25519_instruction(&signer.pubkey(), &signature, &message),
+            ix(
+                self.solve_raffle_accounts(raffle),
+                instruction::SolveRaffle { sig: signature.as_ref().to_vec(), seed },
+            ),
+        ]
+    }
+
+    pub async fn solve_raffle(&mut self, raffle: &Pubkey, seed: [u8; 32]) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let instructions = self.solve_raffle_instructions(raffle, &maker, seed).await;
+
+        self.send(&instructions, &[&maker]).await
+    }
+
+    pub async fn scratch_ticket(&mut self, raffle: &Pubkey, buyer: &Keypair, ticket: &Pubkey) -> Result<(), BanksClientError> {
+        let scratch_ticket = scratch_ticket(&self.keys(raffle), &RaffleOptions::default(), &buyer.pubkey(), ticket);
+
+        self.send(&[scratch_ticket], &[buyer]).await
+    }
+
+    pub async fn refund_ticket(&mut self, raffle: &Pubkey, buyer: &Keypair, ticket: &Pubkey) -> Result<(), BanksClientError> {
+        let refund_ticket = refund_ticket(&self.keys(raffle), &RaffleOptions::default(), &buyer.pubkey(), ticket);
+
+        self.send(&[refund_ticket], &[buyer]).await
+    }
+
+    pub async fn cancel_raffle(&mut self, raffle: &Pubkey) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let cancel_raffle = cancel_raffle(&self.keys(raffle), &RaffleOptions::default());
+
+        self.send(&[cancel_raffle], &[&maker]).await
+    }
+
+    pub async fn create_asset(&mut self) -> Pubkey {
+        let asset = Keypair::new();
+        let maker = self.maker.insecure_clone();
+
+        let create = mpl_core::instructions::CreateV1Builder::new()
+            .asset(asset.pubkey())
+            .payer(maker.pubkey())
+            .owner(Some(maker.pubkey()))
+            .name("Prize".to_string())
+            .uri("https://example.com/prize.json".to_string())
+            .instruction();
+
+        self.send(&[create], &[&maker, &asset]).await.unwrap();
+
+        asset.pubkey()
+    }
+
+    // A raffle whose first place wins an mpl-core asset escrowed by the maker
+    pub async fn create_raffle_with_prize(&mut self) -> (Pubkey, Pubkey) {
+        let asset = self.create_asset().await;
+        let raffle = Keypair::new();
+
+        let mut args = self.raffle_args();
+        args.prize = Prize::Asset { asset };
+
+        let accounts = accounts::CreateRaffle {
+            prize_asset: Some(asset),
+            ..self.create_raffle_accounts(&raffle.pubkey())
+        };
+
+        self.create_raffle_with(accounts, args, &raffle).await.unwrap();
+
+        (raffle.pubkey(), asset)
+    }
+
+    pub async fn reclaim_prize(&mut self, raffle: &Pubkey, asset: Option<Pubkey>) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let reclaim_prize = ix(
+            accounts::ReclaimPrize {
+                prize_asset: asset,
+                ..self.reclaim_prize_accounts(raffle)
+            },
+            instruction::ReclaimPrize {},
+        );
+
+        self.send(&[reclaim_prize], &[&maker]).await
+    }
+
+    pub fn reclaim_prize_accounts(&self, raffle: &Pubkey) -> accounts::ReclaimPrize {
+        let house = self.house.pubkey();
+
+        accounts::ReclaimPrize {
+            maker: self.maker.pubkey(),
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            raffle: *raffle,
+            raffle_config: raffle_config(&house, raffle),
+            vault: vault(raffle),
+            prize_asset: None,
+            prize_collection: None,
+            prize_mint: None,
+            vault_prize_token_account: None,
+            maker_prize_token_account: None,
+            mpl_core_program: mpl_core::ID,
+            prize_token_program: None,
+            associated_token_program: None,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        }
+    }
+
+    pub async fn close_raffle(&mut self, raffle: &Pubkey) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+
+        let close_raffle = close_raffle(&self.keys(raffle), &RaffleOptions::default());
+
+        self.send(&[close_raffle], &[&maker]).await
+    }
+
+    pub fn withdraw_treasury_accounts(&self, destination: &Pubkey) -> accounts::WithdrawTreasury {
+        let house = self.house.pubkey();
+
+        accounts::WithdrawTreasury {
+            authority: house,
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            destination: *destination,
+            mint: None,
+            treasury_token_account: None,
+            destination_token_account: None,
+            token_program: None,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        }
+    }
+
+    pub async fn withdraw_treasury(&mut self, destination: &Pubkey, amount: u64) -> Result<(), BanksClientError> {
+        let house = self.house.insecure_clone();
+
+        let withdraw_treasury = ix(
+            self.withdraw_treasury_accounts(destination),
+            instruction::WithdrawTreasury { amount },
+        );
+
+        self.send(&[withdraw_treasury], &[&house]).await
+    }
+
+    // Solves a raffle that has already sold its tickets and returns the winning numbers
+    pub async fn draw(&mut self, raffle: &Pubkey) -> Vec<u32> {
+        let raffle_config = self.raffle_config(raffle).await;
+
+        if !raffle_config.sales_ended(&self.clock().await, raffle_config.tickets_sold) {
+            self.warp_slots(SLOT_INTERVAL + 1).await;
+        }
+
+        self.capture_entropy(raffle).await.unwrap();
+        self.solve_raffle(raffle, SEED).await.unwrap();
+
+        self.raffle_config(raffle).await.winners
+    }
+}
@@ -0,0 +1,984 @@
+// One test per BeeRafError the program can return. Not covered here:
+// - NumericalOverflow and FailedRoll, which guard arithmetic that can not overflow with valid accounts
+// - MissingWinnerAttribute and InvalidTicketData, tickets minted by the program always have valid data
+// - AlreadyClaimed, a ticket is burned when scratched and a number only belongs to one ticket
+// - Ed25519Header, which needs the signature data to live in another instruction
+// - Ed25519Message and NoWinner, which are not returned anymore
+// - CommitmentTooLate, capture_entropy does not schedule the entropy slot without a commitment
+// - EntropyExpired, capture_entropy schedules a new entropy slot before SlotHashes drops the hash
+//
+// The VRF errors are only tested against the program built with the mock oracle:
+// `anchor build -- --features mock-vrf` and `cargo test --features mock-vrf`.
+
+mod common;
+
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    system_instruction, system_program,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, spl_associated_token_account},
+    token::spl_token,
+};
+use beeraf::{
+    accounts, error::BeeRafError, instruction, Prize, RaffleEnd, RafflePolicy, RandomnessSource, UpdateConfigArgs,
+    RANDOMNESS_TIMEOUT,
+};
+use beeraf_client::ed25519_instruction;
+use common::*;
+use solana_sdk::{ed25519_program, program_pack::Pack, pubkey::Pubkey, signature::{Keypair, Signer}};
+
+async fn create_mint(env: &mut TestEnv) -> Pubkey {
+    let mint = Keypair::new();
+    let payer = env.context.payer.pubkey();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &payer, None, 6).unwrap(),
+    ];
+
+    env.send(&instructions, &[&mint]).await.unwrap();
+
+    mint.pubkey()
+}
+
+async fn create_token_account(env: &mut TestEnv, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let create = spl_associated_token_account::instruction::create_associated_token_account(
+        &env.context.payer.pubkey(),
+        owner,
+        mint,
+        &spl_token::ID,
+    );
+
+    env.send(&[create], &[]).await.unwrap();
+
+    get_associated_token_address(owner, mint)
+}
+
+// Ed25519 precompile instruction with several signatures, the client only builds it for one
+fn ed25519_instruction_with(signatures: &[(&Keypair, &[u8])]) -> Instruction {
+    let mut offset = 2 + 14 * signatures.len();
+    let mut offsets = vec![signatures.len() as u8, 0];
+    let mut payload = vec![];
+
+    for (signer, message) in signatures {
+        let public_key_offset = offset as u16;
+        let signature_offset = public_key_offset + 32;
+        let message_data_offset = signature_offset + 64;
+
+        for value in [
+            signature_offset,
+            u16::MAX,
+            public_key_offset,
+            u16::MAX,
+            message_data_offset,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            offsets.extend_from_slice(&value.to_le_bytes());
+        }
+
+        payload.extend_from_slice(&signer.pubkey().to_bytes());
+        payload.extend_from_slice(signer.sign_message(message).as_ref());
+        payload.extend_from_slice(message);
+
+        offset += 32 + 64 + message.len();
+    }
+
+    offsets.extend_from_slice(&payload);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data: offsets,
+    }
+}
+
+async fn open_raffle(env: &mut TestEnv) -> Pubkey {
+    let args = env.raffle_args();
+
+    env.create_raffle(args).await.unwrap()
+}
+
+// A raffle with tickets sold and ticket sales over
+async fn ended_raffle(env: &mut TestEnv, quantity: u32) -> (Pubkey, Keypair, Pubkey) {
+    let raffle = open_raffle(env).await;
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, quantity).await.unwrap();
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    (raffle, buyer, ticket)
+}
+
+#[tokio::test]
+async fn maximum_tickets_reached() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+
+    let result = env.buy_ticket(&raffle, &buyer, CAPACITY + 1).await;
+
+    assert_error(result, BeeRafError::MaximumTicketsReached);
+}
+
+#[tokio::test]
+async fn still_open() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+
+    let result = env.capture_entropy(&raffle).await;
+
+    assert_error(result, BeeRafError::StillOpen);
+}
+
+#[tokio::test]
+async fn no_sold_any_ticket() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    let result = env.solve_raffle(&raffle, SEED).await;
+
+    assert_error(result, BeeRafError::NoSoldAnyTicket);
+}
+
+#[tokio::test]
+async fn ed25519_program() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let instructions = env.solve_raffle_instructions(&raffle, &maker, SEED).await;
+
+    // Without the Ed25519 instruction, the solve instruction itself is the first one
+    let result = env.send(&instructions[1..], &[&maker]).await;
+
+    assert_error(result, BeeRafError::Ed25519Program);
+}
+
+#[tokio::test]
+async fn ed25519_accounts() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let mut instructions = env.solve_raffle_instructions(&raffle, &maker, SEED).await;
+    instructions[0].accounts.push(AccountMeta::new_readonly(raffle, false));
+
+    let result = env.send(&instructions, &[&maker]).await;
+
+    assert_error(result, BeeRafError::Ed25519Accounts);
+}
+
+#[tokio::test]
+async fn ed25519_data_length() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let message = env.raffle_config(&raffle).await.to_slice();
+    let mut instructions = env.solve_raffle_instructions(&raffle, &maker, SEED).await;
+    instructions[0] = ed25519_instruction_with(&[(&maker, &message), (&maker, &message)]);
+
+    let result = env.send(&instructions, &[&maker]).await;
+
+    assert_error(result, BeeRafError::Ed25519DataLength);
+}
+
+#[tokio::test]
+async fn ed25519_pubkey() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, buyer, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let maker = env.maker.insecure_clone();
+
+    // The raffle details signed by someone else than the maker
+    let instructions = env.solve_raffle_instructions(&raffle, &buyer, SEED).await;
+
+    let result = env.send(&instructions, &[&maker]).await;
+
+    assert_error(result, BeeRafError::Ed25519Pubkey);
+}
+
+#[tokio::test]
+async fn ed25519_signature() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let maker = env.maker.insecure_clone();
+    let mut instructions = env.solve_raffle_instructions(&raffle, &maker, SEED).await;
+
+    // A valid signature of the maker, but over another message
+    let message = b"another message";
+    instructions[0] = ed25519_instruction(&maker.pubkey(), &maker.sign_message(message), message);
+
+    let result = env.send(&instructions, &[&maker]).await;
+
+    assert_error(result, BeeRafError::Ed25519Signature);
+}
+
+#[tokio::test]
+async fn time_expired() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    let result = env.buy_ticket(&raffle, &buyer, 1).await;
+
+    assert_error(result, BeeRafError::TimeExpired);
+}
+
+#[tokio::test]
+async fn prize_not_claimed() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.draw(&raffle).await;
+
+    // The winner did not scratch the ticket and the claim period is not over
+    let result = env.close_raffle(&raffle).await;
+
+    assert_error(result, BeeRafError::PrizeNotClaimed);
+}
+
+#[tokio::test]
+async fn insufficient_treasury_funds() {
+    let mut env = TestEnv::start_with_house().await;
+    open_raffle(&mut env).await;
+
+    let house = env.house.pubkey();
+
+    // The treasury must stay rent exempt
+    let result = env.withdraw_treasury(&house, FEE).await;
+
+    assert_error(result, BeeRafError::InsufficientTreasuryFunds);
+}
+
+#[tokio::test]
+async fn invalid_maker() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let someone = env.funded_keypair().await;
+
+    let result = env.migrate_vault(&raffle, &someone).await;
+
+    assert_error(result, BeeRafError::InvalidMaker);
+}
+
+#[tokio::test]
+async fn vault_already_migrated() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let maker = env.maker.insecure_clone();
+
+    let result = env.migrate_vault(&raffle, &maker).await;
+
+    assert_error(result, BeeRafError::VaultAlreadyMigrated);
+}
+
+#[tokio::test]
+async fn missing_attribute() {
+    let (mut env, raffle) = TestEnv::start_with_legacy_raffle(None).await;
+    let maker = env.maker.insecure_clone();
+
+    let result = env.migrate_vault(&raffle, &maker).await;
+
+    assert_error(result, BeeRafError::MissingAttribute);
+}
+
+#[tokio::test]
+async fn invalid_capacity() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.capacity = 0;
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidCapacity);
+}
+
+#[tokio::test]
+async fn invalid_min_tickets() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.min_tickets = Some(CAPACITY + 1);
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidMinTickets);
+}
+
+#[tokio::test]
+async fn min_tickets_not_reached() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.min_tickets = Some(3);
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 2).await.unwrap();
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    let result = env.solve_raffle(&raffle, SEED).await;
+
+    assert_error(result, BeeRafError::MinTicketsNotReached);
+}
+
+#[tokio::test]
+async fn refund_not_available() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    let result = env.refund_ticket(&raffle, &buyer, &ticket).await;
+
+    assert_error(result, BeeRafError::RefundNotAvailable);
+}
+
+#[tokio::test]
+async fn invalid_commitment() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.commitment = [0; 32];
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidCommitment);
+}
+
+#[tokio::test]
+async fn commitment_already_set() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+
+    let result = env.commit_seed(&raffle, [8; 32]).await;
+
+    assert_error(result, BeeRafError::CommitmentAlreadySet);
+}
+
+#[tokio::test]
+async fn invalid_reveal() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let result = env.solve_raffle(&raffle, [8; 32]).await;
+
+    assert_error(result, BeeRafError::InvalidReveal);
+}
+
+#[tokio::test]
+async fn missing_entropy() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+
+    let result = env.solve_raffle(&raffle, SEED).await;
+
+    assert_error(result, BeeRafError::MissingEntropy);
+}
+
+#[tokio::test]
+async fn randomness_timed_out() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    env.warp_slots(RANDOMNESS_TIMEOUT + 1).await;
+
+    let result = env.solve_raffle(&raffle, SEED).await;
+
+    assert_error(result, BeeRafError::RandomnessTimedOut);
+}
+
+#[tokio::test]
+async fn entropy_already_captured() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    let result = env.send_capture_entropy(&raffle).await;
+
+    assert_error(result, BeeRafError::EntropyAlreadyCaptured);
+}
+
+#[tokio::test]
+async fn entropy_not_available() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.send_capture_entropy(&raffle).await.unwrap();
+
+    let result = env.send_capture_entropy(&raffle).await;
+
+    assert_error(result, BeeRafError::EntropyNotAvailable);
+}
+
+#[cfg(not(feature = "vrf"))]
+#[tokio::test]
+async fn invalid_randomness_source() {
+    let mut env = TestEnv::start_with_house().await;
+
+    // The program under test is built without the vrf feature
+    let mut args = env.raffle_args();
+    args.randomness_source = RandomnessSource::Vrf;
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidRandomnessSource);
+}
+
+#[tokio::test]
+async fn invalid_prize_tiers() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.prize_tiers = vec![5_000, 4_000];
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidPrizeTiers);
+}
+
+#[tokio::test]
+async fn already_resolved() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+    env.draw(&raffle).await;
+
+    let result = env.solve_raffle(&raffle, SEED).await;
+
+    assert_error(result, BeeRafError::AlreadyResolved);
+}
+
+#[tokio::test]
+async fn missing_token_accounts() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.payment_mint = Some(Pubkey::new_unique());
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::MissingTokenAccounts);
+}
+
+#[tokio::test]
+async fn invalid_payment_mint() {
+    let mut env = TestEnv::start_with_house().await;
+    let mint = create_mint(&mut env).await;
+    let raffle = Keypair::new();
+
+    let mut args = env.raffle_args();
+    args.payment_mint = Some(Pubkey::new_unique());
+
+    let accounts = accounts::CreateRaffle {
+        payment_mint: Some(mint),
+        vault_token_account: Some(get_associated_token_address(&vault(&raffle.pubkey()), &mint)),
+        treasury_token_account: Some(get_associated_token_address(&treasury(&env.house.pubkey()), &mint)),
+        token_program: Some(spl_token::ID),
+        associated_token_program: Some(spl_associated_token_account::ID),
+        ..env.create_raffle_accounts(&raffle.pubkey())
+    };
+
+    let result = env.create_raffle_with(accounts, args, &raffle).await;
+
+    assert_error(result, BeeRafError::InvalidPaymentMint);
+}
+
+#[tokio::test]
+async fn invalid_token_account() {
+    let mut env = TestEnv::start_with_house().await;
+    let mint = create_mint(&mut env).await;
+
+    let house = env.house.pubkey();
+    let house_keypair = env.house.insecure_clone();
+
+    // The source must be the token account of the treasury
+    let house_token_account = create_token_account(&mut env, &house, &mint).await;
+
+    let withdraw_treasury = ix(
+        accounts::WithdrawTreasury {
+            mint: Some(mint),
+            treasury_token_account: Some(house_token_account),
+            destination_token_account: Some(house_token_account),
+            token_program: Some(spl_token::ID),
+            ..env.withdraw_treasury_accounts(&house)
+        },
+        instruction::WithdrawTreasury { amount: 0 },
+    );
+
+    let result = env.send(&[withdraw_treasury], &[&house_keypair]).await;
+
+    assert_error(result, BeeRafError::InvalidTokenAccount);
+}
+
+#[tokio::test]
+async fn missing_prize_accounts() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.prize = Prize::Asset { asset: Pubkey::new_unique() };
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::MissingPrizeAccounts);
+}
+
+#[tokio::test]
+async fn invalid_prize() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+
+    // There is no escrowed prize to give back
+    let result = env.reclaim_prize(&raffle, None).await;
+
+    assert_error(result, BeeRafError::InvalidPrize);
+}
+
+#[tokio::test]
+async fn prize_already_released() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, asset) = env.create_raffle_with_prize().await;
+
+    // Nothing was sold, so the prize goes back to the maker
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+    env.reclaim_prize(&raffle, Some(asset)).await.unwrap();
+
+    let result = env.reclaim_prize(&raffle, Some(asset)).await;
+
+    assert_error(result, BeeRafError::PrizeAlreadyReleased);
+}
+
+#[tokio::test]
+async fn prize_not_reclaimable() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, asset) = env.create_raffle_with_prize().await;
+
+    let result = env.reclaim_prize(&raffle, Some(asset)).await;
+
+    assert_error(result, BeeRafError::PrizeNotReclaimable);
+}
+
+#[tokio::test]
+async fn invalid_quantity() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+
+    let result = env.buy_ticket(&raffle, &buyer, 0).await;
+
+    assert_error(result, BeeRafError::InvalidQuantity);
+}
+
+#[tokio::test]
+async fn invalid_authority() {
+    let mut env = TestEnv::start_with_house().await;
+    let someone = env.funded_keypair().await;
+    let house = env.house.pubkey();
+
+    let update_config = ix(
+        accounts::UpdateConfig {
+            authority: someone.pubkey(),
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        },
+        instruction::UpdateConfig {
+            args: UpdateConfigArgs {
+                fee: Some(0),
+                house_fee_bps: None,
+                policy: None,
+            },
+        },
+    );
+
+    let result = env.send(&[update_config], &[&someone]).await;
+
+    assert_error(result, BeeRafError::InvalidAuthority);
+
+    // Nobody can accept an authority that was not proposed
+    let accept_authority = ix(
+        accounts::AcceptAuthority {
+            new_authority: someone.pubkey(),
+            house,
+            treasury: treasury(&house),
+            config: config(&house),
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        },
+        instruction::AcceptAuthority {},
+    );
+
+    let result = env.send(&[accept_authority], &[&someone]).await;
+
+    assert_error(result, BeeRafError::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn invalid_fee() {
+    let mut env = TestEnv::start().await;
+
+    let result = env.initialize(FEE, 10_001, default_policy()).await;
+
+    assert_error(result, BeeRafError::InvalidFee);
+}
+
+#[tokio::test]
+async fn invalid_policy() {
+    let mut env = TestEnv::start().await;
+
+    let policy = RafflePolicy {
+        min_ticket_price: Some(TICKET_PRICE),
+        max_ticket_price: Some(TICKET_PRICE - 1),
+        ..default_policy()
+    };
+
+    let result = env.initialize(FEE, HOUSE_FEE_BPS, policy).await;
+
+    assert_error(result, BeeRafError::InvalidPolicy);
+}
+
+#[tokio::test]
+async fn raffle_fee_too_high() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.raffle_fee = 1_001;
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::RaffleFeeTooHigh);
+}
+
+#[tokio::test]
+async fn ticket_price_too_low() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.ticket_price = 0;
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::TicketPriceTooLow);
+}
+
+#[tokio::test]
+async fn ticket_price_too_high() {
+    let mut env = TestEnv::start().await;
+
+    let policy = RafflePolicy {
+        max_ticket_price: Some(TICKET_PRICE - 1),
+        ..default_policy()
+    };
+    env.initialize(FEE, HOUSE_FEE_BPS, policy).await.unwrap();
+
+    let args = env.raffle_args();
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::TicketPriceTooHigh);
+}
+
+#[tokio::test]
+async fn slot_interval_too_short() {
+    let mut env = TestEnv::start().await;
+
+    let policy = RafflePolicy {
+        min_slot_interval: Some(SLOT_INTERVAL + 1),
+        ..default_policy()
+    };
+    env.initialize(FEE, HOUSE_FEE_BPS, policy).await.unwrap();
+
+    let args = env.raffle_args();
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::SlotIntervalTooShort);
+}
+
+#[tokio::test]
+async fn slot_interval_too_long() {
+    let mut env = TestEnv::start().await;
+
+    let policy = RafflePolicy {
+        max_slot_interval: Some(SLOT_INTERVAL - 1),
+        ..default_policy()
+    };
+    env.initialize(FEE, HOUSE_FEE_BPS, policy).await.unwrap();
+
+    let args = env.raffle_args();
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::SlotIntervalTooLong);
+}
+
+#[tokio::test]
+async fn not_started() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let now = env.clock().await.unix_timestamp;
+
+    let mut args = env.raffle_args();
+    args.start_timestamp = Some(now + 3_600);
+    args.end = RaffleEnd::UnixTimestamp { end_timestamp: now + 7_200 };
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let result = env.buy_ticket(&raffle, &buyer, 1).await;
+
+    assert_error(result, BeeRafError::NotStarted);
+}
+
+#[tokio::test]
+async fn invalid_deadline() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let now = env.clock().await.unix_timestamp;
+
+    let mut args = env.raffle_args();
+    args.end = RaffleEnd::UnixTimestamp { end_timestamp: now - 1 };
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidDeadline);
+
+    // The slot interval ends long before the start, at the nominal slot time
+    let mut args = env.raffle_args();
+    args.start_timestamp = Some(now + 3_600);
+
+    let result = env.create_raffle(args).await;
+
+    assert_error(result, BeeRafError::InvalidDeadline);
+}
+
+#[tokio::test]
+async fn raffle_cancelled() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    env.cancel_raffle(&raffle).await.unwrap();
+
+    let result = env.cancel_raffle(&raffle).await;
+
+    assert_error(result, BeeRafError::RaffleCancelled);
+}
+
+#[tokio::test]
+async fn cancel_not_available() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+
+    let result = env.cancel_raffle(&raffle).await;
+
+    assert_error(result, BeeRafError::CancelNotAvailable);
+}
+
+#[tokio::test]
+async fn raffle_not_open() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.capacity = 1;
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    // The raffle sold out
+    let result = env.buy_ticket(&raffle, &buyer, 1).await;
+
+    assert_error(result, BeeRafError::RaffleNotOpen);
+}
+
+#[tokio::test]
+async fn invalid_status() {
+    let mut env = TestEnv::start_with_house().await;
+    let raffle = open_raffle(&mut env).await;
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    let result = env.scratch_ticket(&raffle, &buyer, &ticket).await;
+
+    assert_error(result, BeeRafError::InvalidStatus);
+}
+
+#[cfg(feature = "mock-vrf")]
+mod vrf {
+    use super::*;
+    use anchor_lang::{InstructionData, ToAccountMetas};
+
+    // A VRF raffle with a ticket sold and ticket sales over
+    async fn ended_vrf_raffle(env: &mut TestEnv) -> Pubkey {
+        let mut args = env.raffle_args();
+        args.randomness_source = RandomnessSource::Vrf;
+        args.commitment = [0; 32];
+
+        let raffle = env.create_raffle(args).await.unwrap();
+        let buyer = env.funded_keypair().await;
+        env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+        env.warp_slots(SLOT_INTERVAL + 1).await;
+
+        raffle
+    }
+
+    // Randomness account of the mock oracle, fulfilled right away when asked
+    async fn mock_randomness(env: &mut TestEnv, fulfilled: bool) -> Pubkey {
+        let payer = env.context.payer.pubkey();
+        let seed = Keypair::new().pubkey().to_bytes();
+        let randomness = Pubkey::find_program_address(&[b"randomness", seed.as_ref()], &mock_vrf::ID).0;
+
+        let mut instructions = vec![Instruction {
+            program_id: mock_vrf::ID,
+            accounts: mock_vrf::accounts::Request {
+                payer,
+                randomness,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_vrf::instruction::Request { seed }.data(),
+        }];
+
+        if fulfilled {
+            instructions.push(Instruction {
+                program_id: mock_vrf::ID,
+                accounts: mock_vrf::accounts::Fulfill { authority: payer, randomness }.to_account_metas(None),
+                data: mock_vrf::instruction::Fulfill { randomness: [1; 64] }.data(),
+            });
+        }
+
+        env.send(&instructions, &[]).await.unwrap();
+
+        randomness
+    }
+
+    async fn request_randomness(env: &mut TestEnv, raffle: &Pubkey, randomness: &Pubkey) -> Result<(), solana_program_test::BanksClientError> {
+        let house = env.house.pubkey();
+        let maker = env.maker.insecure_clone();
+
+        let request_randomness = ix(
+            accounts::RequestRandomness {
+                maker: maker.pubkey(),
+                house,
+                treasury: treasury(&house),
+                config: config(&house),
+                raffle: *raffle,
+                raffle_config: raffle_config(&house, raffle),
+                randomness: *randomness,
+                event_authority: event_authority(),
+                program: beeraf::ID,
+            },
+            instruction::RequestRandomness {},
+        );
+
+        env.send(&[request_randomness], &[&maker]).await
+    }
+
+    #[tokio::test]
+    async fn invalid_randomness_source() {
+        let mut env = TestEnv::start_with_house().await;
+        let (raffle, _, _) = ended_raffle(&mut env, 1).await;
+        let randomness = mock_randomness(&mut env, false).await;
+
+        let result = request_randomness(&mut env, &raffle, &randomness).await;
+
+        assert_error(result, BeeRafError::InvalidRandomnessSource);
+    }
+
+    #[tokio::test]
+    async fn invalid_randomness_account() {
+        let mut env = TestEnv::start_with_house().await;
+        let raffle = ended_vrf_raffle(&mut env).await;
+
+        // An already fulfilled request would let the maker pick a known result
+        let randomness = mock_randomness(&mut env, true).await;
+
+        let result = request_randomness(&mut env, &raffle, &randomness).await;
+
+        assert_error(result, BeeRafError::InvalidRandomnessAccount);
+    }
+
+    #[tokio::test]
+    async fn randomness_already_bound() {
+        let mut env = TestEnv::start_with_house().await;
+        let raffle = ended_vrf_raffle(&mut env).await;
+        let randomness = mock_randomness(&mut env, false).await;
+        request_randomness(&mut env, &raffle, &randomness).await.unwrap();
+
+        let other_randomness = mock_randomness(&mut env, false).await;
+
+        let result = request_randomness(&mut env, &raffle, &other_randomness).await;
+
+        assert_error(result, BeeRafError::RandomnessAlreadyBound);
+    }
+
+    #[tokio::test]
+    async fn randomness_not_fulfilled() {
+        let mut env = TestEnv::start_with_house().await;
+        let raffle = ended_vrf_raffle(&mut env).await;
+        let randomness = mock_randomness(&mut env, false).await;
+        request_randomness(&mut env, &raffle, &randomness).await.unwrap();
+
+        let solve_raffle_vrf = ix(
+            accounts::SolveRaffle {
+                randomness: Some(randomness),
+                ..env.solve_raffle_accounts(&raffle)
+            },
+            instruction::SolveRaffleVrf {},
+        );
+        let maker = env.maker.insecure_clone();
+
+        let result = env.send(&[solve_raffle_vrf], &[&maker]).await;
+
+        assert_error(result, BeeRafError::RandomnessNotFulfilled);
+    }
+
+    #[tokio::test]
+    async fn maker_never_requests_randomness() {
+        let mut env = TestEnv::start_with_house().await;
+
+        let mut args = env.raffle_args();
+        args.randomness_source = RandomnessSource::Vrf;
+        args.commitment = [0; 32];
+
+        let raffle = env.create_raffle(args).await.unwrap();
+        let buyer = env.funded_keypair().await;
+        let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+        // The maker stalls past the deadline, so buyers get refunded and the request comes too late
+        env.warp_slots(SLOT_INTERVAL + RANDOMNESS_TIMEOUT + 1).await;
+
+        let randomness = mock_randomness(&mut env, false).await;
+        let result = request_randomness(&mut env, &raffle, &randomness).await;
+
+        assert_error(result, BeeRafError::RandomnessTimedOut);
+
+        env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+        let raffle_config = env.raffle_config(&raffle).await;
+        assert_eq!(raffle_config.status, beeraf::RaffleStatus::Refunding);
+        assert_eq!(raffle_config.refunded_tickets, 1);
+    }
+}
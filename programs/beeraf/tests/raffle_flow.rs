@@ -0,0 +1,558 @@
+mod common;
+
+use anchor_lang::solana_program::system_program;
+use beeraf::{
+    accounts, error::BeeRafError, instruction, Config, RaffleConfig, RaffleEnd, RaffleStatus, UpdateConfigArgs,
+    CLAIM_PERIOD, ENTROPY_DELAY, RAFFLE_CONFIG_VERSION, RANDOMNESS_TIMEOUT, SLOT_HASHES_WINDOW, VAULT_VERSION,
+};
+use common::*;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+#[tokio::test]
+async fn initialize_create_buy_solve_and_scratch() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let house = env.house.pubkey();
+    let config: Config = env.account(&config(&house)).await.unwrap();
+    assert_eq!(config.authority, house);
+    assert_eq!(config.fee, FEE);
+    assert_eq!(config.house_fee_bps, HOUSE_FEE_BPS);
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+    assert_eq!(env.balance(&treasury(&house)).await, FEE);
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Open);
+    assert_eq!(raffle_config.creation_fee, FEE);
+
+    let buyer_a = env.funded_keypair().await;
+    let buyer_b = env.funded_keypair().await;
+
+    let ticket_a = env.buy_ticket(&raffle, &buyer_a, 1).await.unwrap();
+    let ticket_b = env.buy_ticket(&raffle, &buyer_b, 4).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.tickets_sold, 5);
+    assert_eq!(raffle_config.pot, 5 * vault_earning());
+    assert_eq!(raffle_config.escrowed_fees, 5 * TICKET_PRICE * RAFFLE_FEE / 10_000);
+    assert_eq!(raffle_config.escrowed_house_fees, 5 * house_fee());
+    assert_eq!(env.balance(&vault(&raffle)).await, 5 * TICKET_PRICE);
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    env.capture_entropy(&raffle).await.unwrap();
+    assert_eq!(env.raffle_config(&raffle).await.status, RaffleStatus::AwaitingRandomness);
+
+    env.solve_raffle(&raffle, SEED).await.unwrap();
+
+    // The house cut leaves the vault once the raffle is resolved
+    assert_eq!(env.balance(&treasury(&house)).await, FEE + 5 * house_fee());
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Resolved);
+    assert_eq!(raffle_config.escrowed_fees, 0);
+    assert_eq!(raffle_config.escrowed_house_fees, 0);
+    assert_eq!(raffle_config.winners.len(), 1);
+    assert!((1..=5).contains(&raffle_config.winners[0]));
+
+    // Ticket A holds number 1, ticket B numbers 2 to 5
+    let (winner, winning_ticket, loser, losing_ticket) = match raffle_config.winners[0] {
+        1 => (&buyer_a, ticket_a, &buyer_b, ticket_b),
+        _ => (&buyer_b, ticket_b, &buyer_a, ticket_a),
+    };
+
+    let winner_balance = env.balance(&winner.pubkey()).await;
+
+    env.scratch_ticket(&raffle, winner, &winning_ticket).await.unwrap();
+
+    assert!(env.balance(&winner.pubkey()).await >= winner_balance + raffle_config.pot);
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Claimed);
+    assert_eq!(raffle_config.pot, 0);
+
+    env.scratch_ticket(&raffle, loser, &losing_ticket).await.unwrap();
+
+    // Every ticket has been burned, so the collection goes away with the raffle
+    env.close_raffle(&raffle).await.unwrap();
+
+    let raffle_config_account = env.context.banks_client
+        .get_account(common::raffle_config(&house, &raffle))
+        .await
+        .unwrap();
+    assert!(raffle_config_account.is_none());
+}
+
+#[tokio::test]
+async fn prize_tiers_pay_every_place() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.prize_tiers = vec![6_000, 4_000];
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let mut tickets = vec![];
+
+    for _ in 0..3 {
+        let buyer = env.funded_keypair().await;
+        let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+        tickets.push((buyer, ticket));
+    }
+
+    let winners = env.draw(&raffle).await;
+    assert_eq!(winners.len(), 2);
+    assert_ne!(winners[0], winners[1]);
+
+    let prize_pool = env.raffle_config(&raffle).await.prize_pool;
+    assert_eq!(prize_pool, 3 * vault_earning());
+
+    // Ticket numbers follow the order of the purchases
+    let (first_buyer, first_ticket) = &tickets[winners[0] as usize - 1];
+    let first_balance = env.balance(&first_buyer.pubkey()).await;
+
+    env.scratch_ticket(&raffle, first_buyer, first_ticket).await.unwrap();
+
+    assert!(env.balance(&first_buyer.pubkey()).await >= first_balance + prize_pool * 6_000 / 10_000);
+    assert_eq!(env.raffle_config(&raffle).await.status, RaffleStatus::Resolved);
+
+    let (second_buyer, second_ticket) = &tickets[winners[1] as usize - 1];
+
+    env.scratch_ticket(&raffle, second_buyer, second_ticket).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Claimed);
+    assert_eq!(raffle_config.claimed_places, 0b11);
+    assert_eq!(raffle_config.pot, 0);
+}
+
+#[tokio::test]
+async fn sold_out_raffle_solves_before_the_deadline() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.capacity = 2;
+    args.end_on_sell_out = true;
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 2).await.unwrap();
+
+    assert_eq!(env.raffle_config(&raffle).await.status, RaffleStatus::SoldOut);
+
+    env.capture_entropy(&raffle).await.unwrap();
+    env.solve_raffle(&raffle, SEED).await.unwrap();
+
+    assert_eq!(env.raffle_config(&raffle).await.status, RaffleStatus::Resolved);
+}
+
+#[tokio::test]
+async fn unix_timestamp_deadline() {
+    let mut env = TestEnv::start_with_house().await;
+
+    env.set_unix_timestamp(1_700_000_000).await;
+
+    let mut args = env.raffle_args();
+    args.end = RaffleEnd::UnixTimestamp { end_timestamp: 1_700_003_600 };
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.slot, 0);
+    assert_eq!(raffle_config.end_timestamp, 1_700_003_600);
+
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    // Slots alone do not end the raffle
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+    env.set_unix_timestamp(1_700_000_000).await;
+    assert_error(env.capture_entropy(&raffle).await, BeeRafError::StillOpen);
+
+    env.set_unix_timestamp(1_700_003_601).await;
+    env.send_capture_entropy(&raffle).await.unwrap();
+
+    let entropy_slot = env.raffle_config(&raffle).await.entropy_slot;
+    env.warp_to_entropy_slot(entropy_slot).await;
+    env.set_unix_timestamp(1_700_003_601).await;
+
+    env.send_capture_entropy(&raffle).await.unwrap();
+    env.solve_raffle(&raffle, SEED).await.unwrap();
+
+    assert_eq!(env.raffle_config(&raffle).await.winners, vec![1]);
+}
+
+#[tokio::test]
+async fn refund_below_minimum_of_tickets() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.min_tickets = Some(3);
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    // The maker fee stays in the vault until the raffle is resolved
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.escrowed_fees, TICKET_PRICE * RAFFLE_FEE / 10_000);
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+
+    let buyer_balance = env.balance(&buyer.pubkey()).await;
+
+    env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    // The whole price comes back, house cut included
+    assert!(env.balance(&buyer.pubkey()).await >= buyer_balance + TICKET_PRICE);
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Refunding);
+    assert_eq!(raffle_config.refunded_tickets, 1);
+    assert_eq!(raffle_config.pot, 0);
+    assert_eq!(raffle_config.escrowed_fees, 0);
+    assert_eq!(raffle_config.escrowed_house_fees, 0);
+
+    env.close_raffle(&raffle).await.unwrap();
+}
+
+#[tokio::test]
+async fn house_cut_is_fixed_at_creation() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+    assert_eq!(env.raffle_config(&raffle).await.house_fee_bps, HOUSE_FEE_BPS);
+
+    // Raising the cut only applies to the raffles created from now on
+    let house = env.house.insecure_clone();
+    let update_config = ix(
+        accounts::UpdateConfig {
+            authority: house.pubkey(),
+            house: house.pubkey(),
+            treasury: treasury(&house.pubkey()),
+            config: config(&house.pubkey()),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: beeraf::ID,
+        },
+        instruction::UpdateConfig {
+            args: UpdateConfigArgs {
+                fee: None,
+                house_fee_bps: Some(HOUSE_FEE_BPS * 2),
+                policy: None,
+            },
+        },
+    );
+
+    env.send(&[update_config], &[&house]).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.escrowed_house_fees, house_fee());
+    assert_eq!(raffle_config.pot, vault_earning());
+}
+
+#[tokio::test]
+async fn expired_entropy_slot_is_rescheduled() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+    env.send_capture_entropy(&raffle).await.unwrap();
+
+    // Nobody captured the hash of the entropy slot while it was in SlotHashes
+    env.warp_slots(ENTROPY_DELAY + SLOT_HASHES_WINDOW + 1).await;
+
+    // The raffle does not fail, the next call schedules a new entropy slot
+    assert_error(env.refund_ticket(&raffle, &buyer, &ticket).await, BeeRafError::RefundNotAvailable);
+
+    let expired_slot = env.raffle_config(&raffle).await.entropy_slot;
+
+    env.send_capture_entropy(&raffle).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Open);
+    assert!(raffle_config.entropy_slot > expired_slot + SLOT_HASHES_WINDOW);
+
+    env.warp_to_entropy_slot(raffle_config.entropy_slot).await;
+    env.send_capture_entropy(&raffle).await.unwrap();
+    env.solve_raffle(&raffle, SEED).await.unwrap();
+
+    assert_eq!(env.raffle_config(&raffle).await.winners, vec![1]);
+}
+
+#[tokio::test]
+async fn refund_once_randomness_timed_out() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+    env.capture_entropy(&raffle).await.unwrap();
+
+    // The maker never reveals the seed
+    env.warp_slots(RANDOMNESS_TIMEOUT + 1).await;
+
+    env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Refunding);
+    assert_eq!(raffle_config.refunded_tickets, 1);
+}
+
+#[tokio::test]
+async fn cancel_with_tickets_refunds_holders() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 2).await.unwrap();
+
+    env.cancel_raffle(&raffle).await.unwrap();
+    assert_eq!(env.raffle_config(&raffle).await.status, RaffleStatus::Cancelled);
+
+    let buyer_balance = env.balance(&buyer.pubkey()).await;
+
+    env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    // The maker fee and the house cut are refunded with the pot, plus the rent of the burned ticket
+    assert!(env.balance(&buyer.pubkey()).await >= buyer_balance + 2 * TICKET_PRICE);
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.refunded_tickets, 2);
+    assert_eq!(raffle_config.pot, 0);
+    assert_eq!(raffle_config.escrowed_fees, 0);
+    assert_eq!(raffle_config.escrowed_house_fees, 0);
+    assert_eq!(env.balance(&vault(&raffle)).await, 0);
+
+    env.close_raffle(&raffle).await.unwrap();
+}
+
+#[tokio::test]
+async fn cancel_without_tickets_gives_back_part_of_the_fee() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let house = env.house.pubkey();
+    let maker = env.maker.pubkey();
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let maker_balance = env.balance(&maker).await;
+
+    env.cancel_raffle(&raffle).await.unwrap();
+
+    // Half of the fee per the default policy, plus the rent of the raffle config
+    assert!(env.balance(&maker).await >= maker_balance + FEE / 2);
+    assert_eq!(env.balance(&treasury(&house)).await, FEE - FEE / 2);
+
+    let raffle_config_account = env.context.banks_client
+        .get_account(raffle_config(&house, &raffle))
+        .await
+        .unwrap();
+    assert!(raffle_config_account.is_none());
+}
+
+#[tokio::test]
+async fn withdraw_treasury_keeps_it_rent_exempt() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let house = env.house.pubkey();
+
+    let args = env.raffle_args();
+    env.create_raffle(args).await.unwrap();
+
+    let house_balance = env.balance(&house).await;
+
+    env.withdraw_treasury(&house, FEE / 2).await.unwrap();
+
+    assert_eq!(env.balance(&house).await, house_balance + FEE / 2);
+    assert_eq!(env.balance(&treasury(&house)).await, FEE - FEE / 2);
+}
+
+#[tokio::test]
+async fn legacy_raffle_migrates_to_its_own_vault() {
+    let (mut env, raffle) = TestEnv::start_with_legacy_raffle(Some(CAPACITY)).await;
+
+    let house = env.house.pubkey();
+    let maker = env.maker.insecure_clone();
+
+    env.migrate_vault(&raffle, &maker).await.unwrap();
+
+    let raffle_config_account = env.context.banks_client
+        .get_account(raffle_config(&house, &raffle))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(raffle_config_account.data.len(), RaffleConfig::INIT_SPACE);
+
+    // No ticket was sold, so nothing moves out of the maker vault
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.vault_version, VAULT_VERSION);
+    assert_eq!(raffle_config.version, RAFFLE_CONFIG_VERSION);
+    assert_eq!(raffle_config.status, RaffleStatus::Open);
+    assert_eq!(raffle_config.capacity, CAPACITY);
+    assert_eq!(raffle_config.pot, 0);
+
+    // Legacy raffles were created without a house cut and keep selling without one
+    assert_eq!(raffle_config.house_fee_bps, 0);
+
+    // Tickets sold from now on are paid into the vault of the raffle
+    let buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    assert_eq!(env.balance(&vault(&raffle)).await, TICKET_PRICE);
+    assert_eq!(env.raffle_config(&raffle).await.pot, vault_earning() + house_fee());
+}
+
+#[tokio::test]
+async fn legacy_raffle_keeps_what_is_left_of_a_short_vault() {
+    // Every legacy ticket paid the ticket price minus the maker fee into the maker vault,
+    // but a winner of another raffle was paid from it, so it only holds two of the three
+    let share = TICKET_PRICE - TICKET_PRICE * RAFFLE_FEE / 10_000;
+    let (mut env, raffle) = TestEnv::start_with_legacy_sales(Some(CAPACITY), 3, 2 * share).await;
+
+    let maker = env.maker.insecure_clone();
+
+    env.migrate_vault(&raffle, &maker).await.unwrap();
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.vault_version, VAULT_VERSION);
+    assert_eq!(raffle_config.pot, 2 * share);
+    assert_eq!(env.balance(&vault(&raffle)).await, 2 * share);
+    assert_eq!(env.balance(&legacy_vault(&maker.pubkey())).await, 0);
+}
+
+// Closes the raffle once the claim period is over, and returns what the maker and the treasury got
+async fn close_after_claim_period(env: &mut TestEnv, raffle: &Pubkey) -> (u64, u64) {
+    let house = env.house.pubkey();
+    let maker = env.maker.pubkey();
+
+    env.warp_slots(CLAIM_PERIOD + 1).await;
+
+    let maker_balance = env.balance(&maker).await;
+    let treasury_balance = env.balance(&treasury(&house)).await;
+    let rent = env.balance(&raffle_config(&house, raffle)).await;
+
+    env.close_raffle(raffle).await.unwrap();
+
+    (
+        env.balance(&maker).await - maker_balance - rent,
+        env.balance(&treasury(&house)).await - treasury_balance,
+    )
+}
+
+#[tokio::test]
+async fn resolved_raffle_sweeps_unclaimed_prizes_to_the_maker() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    for _ in 0..2 {
+        let buyer = env.funded_keypair().await;
+        env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+    }
+
+    // The winners are drawn well after the deadline
+    env.warp_slots(SLOT_INTERVAL + 1_000).await;
+    env.draw(&raffle).await;
+
+    // The claim period runs from the draw, not from the deadline
+    env.warp_slots(CLAIM_PERIOD - 500).await;
+    assert_error(env.close_raffle(&raffle).await, BeeRafError::PrizeNotClaimed);
+
+    // Nobody scratched the winning ticket, the house cut already left with the draw
+    let (to_maker, to_treasury) = close_after_claim_period(&mut env, &raffle).await;
+
+    assert_eq!(to_maker, 2 * vault_earning());
+    assert_eq!(to_treasury, 0);
+}
+
+#[tokio::test]
+async fn failed_raffle_sweeps_unrefunded_tickets_to_the_treasury() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let mut args = env.raffle_args();
+    args.min_tickets = Some(3);
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    let idle_buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &idle_buyer, 1).await.unwrap();
+
+    env.warp_slots(SLOT_INTERVAL + 1).await;
+    env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    // The other buyer never asks for a refund: the maker only gets its maker fee back
+    let (to_maker, to_treasury) = close_after_claim_period(&mut env, &raffle).await;
+
+    assert_eq!(to_maker, TICKET_PRICE * RAFFLE_FEE / 10_000);
+    assert_eq!(to_treasury, vault_earning() + house_fee());
+}
+
+#[tokio::test]
+async fn cancelled_raffle_sweeps_unrefunded_tickets_to_the_treasury() {
+    let mut env = TestEnv::start_with_house().await;
+
+    let args = env.raffle_args();
+    let raffle = env.create_raffle(args).await.unwrap();
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    let idle_buyer = env.funded_keypair().await;
+    env.buy_ticket(&raffle, &idle_buyer, 1).await.unwrap();
+
+    env.cancel_raffle(&raffle).await.unwrap();
+    env.refund_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    // Refunds stay open for the whole claim period
+    assert_error(env.close_raffle(&raffle).await, BeeRafError::PrizeNotClaimed);
+
+    let (to_maker, to_treasury) = close_after_claim_period(&mut env, &raffle).await;
+
+    assert_eq!(to_maker, TICKET_PRICE * RAFFLE_FEE / 10_000);
+    assert_eq!(to_treasury, vault_earning() + house_fee());
+}
+
+#[tokio::test]
+async fn first_place_is_paid_after_the_prize_was_reclaimed() {
+    let mut env = TestEnv::start_with_house().await;
+    let (raffle, asset) = env.create_raffle_with_prize().await;
+
+    let buyer = env.funded_keypair().await;
+    let ticket = env.buy_ticket(&raffle, &buyer, 1).await.unwrap();
+
+    env.draw(&raffle).await;
+
+    // The first place did not claim the prize in time
+    env.warp_slots(CLAIM_PERIOD + 1).await;
+    env.reclaim_prize(&raffle, Some(asset)).await.unwrap();
+
+    let buyer_balance = env.balance(&buyer.pubkey()).await;
+
+    env.scratch_ticket(&raffle, &buyer, &ticket).await.unwrap();
+
+    assert!(env.balance(&buyer.pubkey()).await >= buyer_balance + vault_earning());
+
+    let raffle_config = env.raffle_config(&raffle).await;
+    assert_eq!(raffle_config.status, RaffleStatus::Claimed);
+    assert_eq!(raffle_config.pot, 0);
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+declare_id!("4fnhqN7DczoSSPb9CXUG9ejC47xkMwVbCKcuwoLLVXfL");
+
+// Mock of an ORAO-style VRF oracle. The randomness account keeps the same layout
+// (discriminator, seed, randomness) so beeraf can read it without the real oracle.
+#[program]
+pub mod mock_vrf {
+    use super::*;
+
+    // It will create an unfulfilled randomness account for the seed
+    pub fn request(ctx: Context<Request>, seed: [u8; 32]) -> Result<()> {
+        ctx.accounts.randomness.set_inner(Randomness {
+            seed,
+            randomness: [0; 64],
+        });
+
+        Ok(())
+    }
+
+    // It will reveal the randomness, as the oracle would do
+    pub fn fulfill(ctx: Context<Fulfill>, randomness: [u8; 64]) -> Result<()> {
+        ctx.accounts.randomness.randomness = randomness;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(seed: [u8; 32])]
+pub struct Request<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"randomness", seed.as_ref()],
+        space = Randomness::INIT_SPACE,
+        bump
+    )]
+    pub randomness: Account<'info, Randomness>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Fulfill<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub randomness: Account<'info, Randomness>,
+}
+
+#[account]
+pub struct Randomness {
+    pub seed: [u8; 32],
+    pub randomness: [u8; 64],
+}
+
+impl Randomness {
+    pub const INIT_SPACE: usize = 8 + 32 + 64;
+}
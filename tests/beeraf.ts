@@ -1,8 +1,8 @@
 import * as anchor from "@coral-xyz/anchor";
 import { BN, Program } from "@coral-xyz/anchor";
 import { Beeraf } from "../target/types/beeraf";
-import { Ed25519Program, Keypair, LAMPORTS_PER_SOL, PublicKey, sendAndConfirmTransaction, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction } from "@solana/web3.js";
-import { randomBytes } from "crypto";
+import { Ed25519Program, Keypair, LAMPORTS_PER_SOL, PublicKey, sendAndConfirmTransaction, SYSVAR_INSTRUCTIONS_PUBKEY, SYSVAR_SLOT_HASHES_PUBKEY, Transaction } from "@solana/web3.js";
+import { createHash, randomBytes } from "crypto";
 
 const coreProgram = new PublicKey("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d")
 
@@ -41,7 +41,7 @@ describe("beeraf", () => {
     house.publicKey.toBuffer(),
     raffle.publicKey.toBuffer()
   ], program.programId)[0];
-  let vaultPDA = PublicKey.findProgramAddressSync([Buffer.from("vault"), maker.publicKey.toBuffer()], program.programId)[0];
+  let vaultPDA = PublicKey.findProgramAddressSync([Buffer.from("vault"), raffle.publicKey.toBuffer()], program.programId)[0];
 
   console.log('treasuryPDA', treasuryPDA);
   console.log('configPDA', configPDA);
@@ -58,6 +58,27 @@ describe("beeraf", () => {
 
   const slotInterval = new BN(6);
 
+  // The house takes 2% of every ticket sold
+  const houseFeeBps = 200;
+
+  // Bounds every raffle of the house must respect
+  const policy = {
+    maxRaffleFeeBps: 1000,
+    minTicketPrice: new BN(LAMPORTS_PER_SOL / 100),
+    maxTicketPrice: null,
+    minSlotInterval: null,
+    maxSlotInterval: new BN(1_512_000),
+    // Half of the creation fee is given back when a raffle is cancelled without tickets
+    cancelRefundBps: 5000,
+  };
+
+  // Maximum number of tickets that can be sold
+  const capacity = 1000;
+
+  // The maker commits to a secret seed and reveals it when solving the raffle
+  const seed = randomBytes(32);
+  const commitment = Array.from(createHash("sha256").update(seed).digest());
+
   it("Airdrop", async () => {
     await Promise.all([house, maker, userA, userB, userC, mintRaffle].map(async (k) => {
       return await connection.requestAirdrop(
@@ -78,7 +99,7 @@ describe("beeraf", () => {
   // - userC: Person who buy ticket
   it("Is initialized!", async () => {
     // Add your test here.
-    const tx = await program.methods.initialize(fee)
+    const tx = await program.methods.initialize(fee, houseFeeBps, policy)
       .accounts({
         house: house.publicKey,
       })
@@ -100,7 +121,17 @@ describe("beeraf", () => {
       uri: "https://example.com",
       ticketPrice,
       raffleFee,
-      slotInterval
+      end: { slot: { slotInterval } },
+      startTimestamp: null,
+      endOnSellOut: false,
+      capacity,
+      minTickets: null,
+      commitment,
+      randomnessSource: { commitReveal: {} },
+      // 1st place gets 70% and 2nd place 30% of the pot
+      prizeTiers: [7000, 3000],
+      paymentMint: null,
+      prize: { none: {} },
     };
 
     const tx = await program.methods.createRaffle(createRaffleArgs)
@@ -131,6 +162,7 @@ describe("beeraf", () => {
     const buyTicketArgs  = {
       name: "Raffle Test Ticket",
       uri: "https://example.com",
+      quantity: 1,
     };
 
     let makerBalance = await connection.getBalance(maker.publicKey);
@@ -140,7 +172,6 @@ describe("beeraf", () => {
     .accountsPartial({
       buyer: userA.publicKey,
       house: house.publicKey,
-      maker: maker.publicKey,
       treasury: treasuryPDA,
       config: configPDA,
       raffle: raffle.publicKey,
@@ -163,33 +194,36 @@ describe("beeraf", () => {
   });
 
   it('should be able to buy many tickets', async () => {
+    // A single asset holds the ticket numbers 2 to 6
     const buyTicketArgs  = {
       name: "Raffle Test Ticket",
       uri: "https://example.com",
+      quantity: 5,
     };
 
-    for(let i = 0; i < 5; i++) {
-      const ticket = Keypair.generate(); 
+    const ticket = Keypair.generate(); 
 
-      const tx = await program.methods.buyTicket(buyTicketArgs)
-      .accountsPartial({
-        buyer: userA.publicKey,
-        house: house.publicKey,
-        maker: maker.publicKey,
-        treasury: treasuryPDA,
-        config: configPDA,
-        raffle: raffle.publicKey,
-        raffleConfig: raffleConfigPDA,
-        vault: vaultPDA,
-        ticket: ticket.publicKey,
-        systemProgram: anchor.web3.SystemProgram.programId,
-        mplCoreProgram: coreProgram,
-      })
-      .signers([userA, ticket])
-      .rpc()
-      .then(confirm)
-      .then(log);
-    }
+    const tx = await program.methods.buyTicket(buyTicketArgs)
+    .accountsPartial({
+      buyer: userA.publicKey,
+      house: house.publicKey,
+      treasury: treasuryPDA,
+      config: configPDA,
+      raffle: raffle.publicKey,
+      raffleConfig: raffleConfigPDA,
+      vault: vaultPDA,
+      ticket: ticket.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+      mplCoreProgram: coreProgram,
+    })
+    .signers([userA, ticket])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    const raffleConfigData = await program.account.raffleConfig.fetch(raffleConfigPDA);
+    console.log('ticketsSold: ', raffleConfigData.ticketsSold);
+    console.log('status: ', raffleConfigData.status);
 
     const makerBalance = await connection.getBalance(maker.publicKey);
     console.log('makerBalance: ', makerBalance);
@@ -201,6 +235,36 @@ describe("beeraf", () => {
 
   it('should be able to resolve the raffle and save the winner number', async () => {
     await delay(1000); // 1 seg
+
+    const captureAccounts = {
+      signer: userB.publicKey,
+      house: house.publicKey,
+      treasury: treasuryPDA,
+      config: configPDA,
+      raffle: raffle.publicKey,
+      raffleConfig: raffleConfigPDA,
+      slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+    };
+
+    // The first call schedules the entropy slot
+    await program.methods.captureEntropy()
+    .accountsPartial(captureAccounts)
+    .signers([userB])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    const { entropySlot } = await program.account.raffleConfig.fetch(raffleConfigPDA);
+
+    // and the second one captures its hash once it is in SlotHashes
+    await waitForSlot(entropySlot.toNumber() + 1);
+
+    await program.methods.captureEntropy()
+    .accountsPartial(captureAccounts)
+    .signers([userB])
+    .rpc()
+    .then(confirm)
+    .then(log);
     
     let raffleConfigAccount = await connection.getAccountInfo(raffleConfigPDA, "confirmed");
     
@@ -220,7 +284,7 @@ describe("beeraf", () => {
       message // : raffleConfigAccount.data.subarray(8) // It will slice the data to get all data after the `discriminator`!? 
     });
 
-    const solve_ix = await program.methods.solveRaffle(Buffer.from(sig_ix.data.buffer.slice(16+32, 16+32+64)))    
+    const solve_ix = await program.methods.solveRaffle(Buffer.from(sig_ix.data.buffer.slice(16+32, 16+32+64)), Array.from(seed))    
     .accountsPartial({
       maker: maker.publicKey,
       house: house.publicKey,
@@ -228,6 +292,8 @@ describe("beeraf", () => {
       config: configPDA,
       raffle: raffle.publicKey,
       raffleConfig: raffleConfigPDA,
+      vault: vaultPDA,
+      randomness: null,
       systemProgram: anchor.web3.SystemProgram.programId,
       instructionSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
       mplCoreProgram: coreProgram,
@@ -237,28 +303,27 @@ describe("beeraf", () => {
 
     const tx = new Transaction().add(sig_ix).add(solve_ix);
 
-    let evenListener: number; 
-
     try {
-
-      let betResult: number; 
-
-      evenListener = program.addEventListener('rafEvent', (event) => {
-        betResult = event.winner;
-      });
-
-      await sendAndConfirmTransaction(
+      const signature = await sendAndConfirmTransaction(
         program.provider.connection,
         tx,
         [maker]
       ).then(log);
 
-      console.log(betResult);
+      // The event is sent through a self-CPI, the last inner instruction of the transaction
+      const transaction = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+      const eventIx = transaction.meta.innerInstructions.flatMap((inner) => inner.instructions).at(-1);
+      const eventData = anchor.utils.bytes.bs58.decode(eventIx.data).subarray(8);
+      const event = program.coder.events.decode(anchor.utils.bytes.base64.encode(Buffer.from(eventData)));
+
+      console.log('event: ', event.name);
+      console.log('winners: ', event.data.winners);
+
+      const raffleConfigData = await program.account.raffleConfig.fetch(raffleConfigPDA);
+      console.log('status: ', raffleConfigData.status);
     }catch(err) {
       console.log(err);
       throw Error("It should not fail the program!");
-    } finally {
-      program.removeEventListener(evenListener);
     }
   });
 
@@ -281,7 +346,7 @@ describe("beeraf", () => {
           systemProgram: anchor.web3.SystemProgram.programId,
           mplCoreProgram: coreProgram,
         })
-        .signers([userA, ticketA])
+        .signers([userA])
         .rpc()
         .then(confirm)
         .then(log);
@@ -293,8 +358,279 @@ describe("beeraf", () => {
       throw new Error(err);
     }
   });
+
+  it('should let the house withdraw the fees from the treasury', async () => {
+    let treasuryBalance = await connection.getBalance(treasuryPDA);
+    console.log('treasuryBalance: ', treasuryBalance);
+
+    const tx = await program.methods.withdrawTreasury(fee.divn(2))
+      .accountsPartial({
+        authority: house.publicKey,
+        house: house.publicKey,
+        treasury: treasuryPDA,
+        config: configPDA,
+        destination: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([house])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    treasuryBalance = await connection.getBalance(treasuryPDA);
+    console.log('treasuryBalance: ', treasuryBalance);
+  });
+
+  it('should not initialize the house twice', async () => {
+    try {
+      await program.methods.initialize(new BN(0), 0, policy)
+        .accounts({
+          house: house.publicKey,
+        })
+        .signers([house])
+        .rpc();
+    } catch(err) {
+      console.log('initialize again failed as expected');
+      return;
+    }
+
+    throw new Error('the config has been initialized twice');
+  });
+
+  it('should let the house update the fee', async () => {
+    const tx = await program.methods.updateConfig({
+      fee: fee.muln(2),
+      houseFeeBps: 300,
+      policy: null,
+    })
+      .accountsPartial({
+        authority: house.publicKey,
+        house: house.publicKey,
+        treasury: treasuryPDA,
+        config: configPDA,
+      })
+      .signers([house])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const configData = await program.account.config.fetch(configPDA);
+    console.log('fee: ', configData.fee.toString());
+  });
+
+  it('should transfer the house authority in two steps', async () => {
+    await program.methods.proposeAuthority(userC.publicKey)
+      .accountsPartial({
+        authority: house.publicKey,
+        house: house.publicKey,
+        treasury: treasuryPDA,
+        config: configPDA,
+      })
+      .signers([house])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await program.methods.acceptAuthority()
+      .accountsPartial({
+        newAuthority: userC.publicKey,
+        house: house.publicKey,
+        treasury: treasuryPDA,
+        config: configPDA,
+      })
+      .signers([userC])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const configData = await program.account.config.fetch(configPDA);
+    console.log('authority: ', configData.authority.toBase58());
+  });
+
+  it('should reject a raffle fee above the house policy', async () => {
+    const otherRaffle = Keypair.generate();
+
+    try {
+      await program.methods.createRaffle({
+        name: "Raffle Test Collection",
+        uri: "https://example.com",
+        ticketPrice,
+        raffleFee: new BN(policy.maxRaffleFeeBps + 1),
+        // Ends in one hour, at a unix timestamp
+        end: { unixTimestamp: { endTimestamp: new BN(Math.floor(Date.now() / 1000) + 3600) } },
+        startTimestamp: null,
+        endOnSellOut: false,
+        capacity,
+        minTickets: null,
+        commitment,
+        randomnessSource: { commitReveal: {} },
+        prizeTiers: [],
+        paymentMint: null,
+        prize: { none: {} },
+      })
+      .accountsPartial({
+        maker: maker.publicKey,
+        house: house.publicKey,
+        treasury: treasuryPDA,
+        config: configPDA,
+        raffle: otherRaffle.publicKey,
+        raffleConfig: PublicKey.findProgramAddressSync([
+          Buffer.from("raffle"),
+          house.publicKey.toBuffer(),
+          otherRaffle.publicKey.toBuffer()
+        ], program.programId)[0],
+        vault: PublicKey.findProgramAddressSync([Buffer.from("vault"), otherRaffle.publicKey.toBuffer()], program.programId)[0],
+        mplCoreProgram: coreProgram,
+      })
+      .signers([maker, otherRaffle])
+      .rpc();
+    } catch(err) {
+      console.log('raffle fee rejected as expected');
+      return;
+    }
+
+    throw new Error('the raffle fee is above the house policy');
+  });
+
+  it('should end a sold out raffle before the deadline', async () => {
+    const soldOutRaffle = Keypair.generate();
+    const ticket = Keypair.generate();
+
+    const soldOutAccounts = {
+      house: house.publicKey,
+      treasury: treasuryPDA,
+      config: configPDA,
+      raffle: soldOutRaffle.publicKey,
+      raffleConfig: PublicKey.findProgramAddressSync([
+        Buffer.from("raffle"),
+        house.publicKey.toBuffer(),
+        soldOutRaffle.publicKey.toBuffer()
+      ], program.programId)[0],
+    };
+    const soldOutVault = PublicKey.findProgramAddressSync([Buffer.from("vault"), soldOutRaffle.publicKey.toBuffer()], program.programId)[0];
+
+    await program.methods.createRaffle({
+      name: "Raffle Sold Out Collection",
+      uri: "https://example.com",
+      ticketPrice,
+      raffleFee,
+      // The deadline is far away, but the raffle ends once its 2 tickets are sold
+      end: { slot: { slotInterval: new BN(100_000) } },
+      startTimestamp: null,
+      endOnSellOut: true,
+      capacity: 2,
+      minTickets: null,
+      commitment,
+      randomnessSource: { commitReveal: {} },
+      prizeTiers: [],
+      paymentMint: null,
+      prize: { none: {} },
+    })
+    .accountsPartial({
+      ...soldOutAccounts,
+      maker: maker.publicKey,
+      vault: soldOutVault,
+      mplCoreProgram: coreProgram,
+    })
+    .signers([maker, soldOutRaffle])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    await program.methods.buyTicket({
+      name: "Raffle Sold Out Ticket",
+      uri: "https://example.com",
+      quantity: 2,
+    })
+    .accountsPartial({
+      ...soldOutAccounts,
+      buyer: userB.publicKey,
+      vault: soldOutVault,
+      ticket: ticket.publicKey,
+      mplCoreProgram: coreProgram,
+    })
+    .signers([userB, ticket])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    // Scheduling the entropy slot only works once ticket sales are over
+    await program.methods.captureEntropy()
+    .accountsPartial({
+      ...soldOutAccounts,
+      signer: userB.publicKey,
+      slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+    })
+    .signers([userB])
+    .rpc()
+    .then(confirm)
+    .then(log);
+  });
+
+  it('should let the maker cancel a raffle without tickets', async () => {
+    const cancelledRaffle = Keypair.generate();
+
+    const cancelledAccounts = {
+      house: house.publicKey,
+      treasury: treasuryPDA,
+      config: configPDA,
+      raffle: cancelledRaffle.publicKey,
+      raffleConfig: PublicKey.findProgramAddressSync([
+        Buffer.from("raffle"),
+        house.publicKey.toBuffer(),
+        cancelledRaffle.publicKey.toBuffer()
+      ], program.programId)[0],
+      maker: maker.publicKey,
+      vault: PublicKey.findProgramAddressSync([Buffer.from("vault"), cancelledRaffle.publicKey.toBuffer()], program.programId)[0],
+      mplCoreProgram: coreProgram,
+    };
+
+    await program.methods.createRaffle({
+      name: "Raffle Cancelled Collection",
+      uri: "https://example.com",
+      ticketPrice,
+      raffleFee,
+      end: { slot: { slotInterval: new BN(100_000) } },
+      startTimestamp: null,
+      endOnSellOut: false,
+      capacity,
+      minTickets: null,
+      commitment,
+      randomnessSource: { commitReveal: {} },
+      prizeTiers: [],
+      paymentMint: null,
+      prize: { none: {} },
+    })
+    .accountsPartial(cancelledAccounts)
+    .signers([maker, cancelledRaffle])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    let makerBalance = await connection.getBalance(maker.publicKey);
+    console.log('makerBalance: ', makerBalance);
+
+    await program.methods.cancelRaffle()
+    .accountsPartial(cancelledAccounts)
+    .signers([maker])
+    .rpc()
+    .then(confirm)
+    .then(log);
+
+    makerBalance = await connection.getBalance(maker.publicKey);
+    console.log('makerBalance: ', makerBalance);
+
+    const raffleConfigAccount = await connection.getAccountInfo(cancelledAccounts.raffleConfig);
+    console.log('raffle config closed: ', raffleConfigAccount === null);
+  });
 });
 
 function delay(ms) {
   return new Promise(resolve => setTimeout(resolve, ms));
+}
+
+async function waitForSlot(slot) {
+  while (await anchor.getProvider().connection.getSlot("confirmed") <= slot) {
+    await delay(400);
+  }
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { Beeraf } from "../../target/types/beeraf";
+import { MockVrf } from "../../target/types/mock_vrf";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { randomBytes } from "crypto";
+
+const coreProgram = new PublicKey("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d")
+
+// These tests need beeraf built with the mock oracle:
+// anchor build -- --features mock-vrf && anchor run test-vrf
+describe("beeraf vrf", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.getProvider();
+
+  const connection = provider.connection;
+
+  const program = anchor.workspace.Beeraf as Program<Beeraf>;
+  const mockVrf = anchor.workspace.MockVrf as Program<MockVrf>;
+
+  const confirm = async (signature: string): Promise<string> => {
+    const block = await connection.getLatestBlockhash();
+    await connection.confirmTransaction({
+      signature,
+      ...block,
+    });
+    return signature;
+  };
+
+  const [house, maker, userA, raffle, ticketA] = Array.from({ length: 5 }, () =>
+    Keypair.generate()
+  );
+
+  let treasuryPDA = PublicKey.findProgramAddressSync([Buffer.from("treasury"), house.publicKey.toBuffer()], program.programId)[0];
+  let configPDA = PublicKey.findProgramAddressSync([Buffer.from("config"), treasuryPDA.toBuffer()], program.programId)[0];
+  let raffleConfigPDA = PublicKey.findProgramAddressSync([
+    Buffer.from("raffle"),
+    house.publicKey.toBuffer(),
+    raffle.publicKey.toBuffer()
+  ], program.programId)[0];
+  let vaultPDA = PublicKey.findProgramAddressSync([Buffer.from("vault"), raffle.publicKey.toBuffer()], program.programId)[0];
+
+  const vrfSeed = randomBytes(32);
+  let randomnessPDA = PublicKey.findProgramAddressSync([Buffer.from("randomness"), vrfSeed], mockVrf.programId)[0];
+
+  const accounts = {
+    house: house.publicKey,
+    treasury: treasuryPDA,
+    config: configPDA,
+    raffle: raffle.publicKey,
+    raffleConfig: raffleConfigPDA,
+  };
+
+  it("Airdrop", async () => {
+    await Promise.all([house, maker, userA].map(async (k) => {
+      return await connection.requestAirdrop(
+        k.publicKey,
+        1000 * anchor.web3.LAMPORTS_PER_SOL
+      )
+      .then(confirm);
+    }));
+  });
+
+  it("creates a VRF raffle and sells a ticket", async () => {
+    await program.methods.initialize(new BN(1 * LAMPORTS_PER_SOL), 0, {
+      maxRaffleFeeBps: null,
+      minTicketPrice: null,
+      maxTicketPrice: null,
+      minSlotInterval: null,
+      maxSlotInterval: null,
+      cancelRefundBps: null,
+    })
+      .accounts({
+        house: house.publicKey,
+      })
+      .signers([house])
+      .rpc()
+      .then(confirm);
+
+    await program.methods.createRaffle({
+      name: "Raffle VRF Collection",
+      uri: "https://example.com",
+      ticketPrice: new BN(1 * LAMPORTS_PER_SOL),
+      raffleFee: new BN(100),
+      end: { slot: { slotInterval: new BN(6) } },
+      startTimestamp: null,
+      endOnSellOut: false,
+      capacity: 10,
+      minTickets: null,
+      commitment: Array(32).fill(0),
+      randomnessSource: { vrf: {} },
+      prizeTiers: [],
+      paymentMint: null,
+      prize: { none: {} },
+    })
+    .accountsPartial({
+      ...accounts,
+      maker: maker.publicKey,
+      vault: vaultPDA,
+      mplCoreProgram: coreProgram,
+    })
+    .signers([maker, raffle])
+    .rpc()
+    .then(confirm);
+
+    await program.methods.buyTicket({
+      name: "Raffle VRF Ticket",
+      uri: "https://example.com",
+      quantity: 1,
+    })
+    .accountsPartial({
+      ...accounts,
+      buyer: userA.publicKey,
+      vault: vaultPDA,
+      ticket: ticketA.publicKey,
+      mplCoreProgram: coreProgram,
+    })
+    .signers([userA, ticketA])
+    .rpc()
+    .then(confirm);
+  });
+
+  it("binds the randomness and solves the raffle with it", async () => {
+    await delay(4000); // wait for the deadline
+
+    await mockVrf.methods.request(Array.from(vrfSeed))
+      .accountsPartial({
+        payer: maker.publicKey,
+        randomness: randomnessPDA,
+      })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods.requestRandomness()
+      .accountsPartial({
+        ...accounts,
+        maker: maker.publicKey,
+        randomness: randomnessPDA,
+      })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await mockVrf.methods.fulfill(Array.from(randomBytes(64)))
+      .accountsPartial({
+        authority: maker.publicKey,
+        randomness: randomnessPDA,
+      })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods.solveRaffleVrf()
+      .accountsPartial({
+        ...accounts,
+        maker: maker.publicKey,
+        vault: vaultPDA,
+        randomness: randomnessPDA,
+        instructionSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        mplCoreProgram: coreProgram,
+      })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    const raffleConfigData = await program.account.raffleConfig.fetch(raffleConfigPDA);
+    console.log(raffleConfigData.randomnessAccount.toBase58());
+  });
+});
+
+function delay(ms) {
+  return new Promise(resolve => setTimeout(resolve, ms));
+}